@@ -14,25 +14,69 @@
 //    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::{
-    cmp, env, fmt, fs,
-    io::{self, Write},
-    path, process,
+    cmp, collections, env, ffi, fmt, fs,
+    io::{self, IsTerminal, Write},
+    num, ops, path, process,
+    sync::atomic,
+    thread, time,
 };
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use colored::Colorize;
 
 mod parsing;
+mod tui;
 use icu_locid::locale;
 use jiff::tz;
-use parsing::{RECORD_SEPARATOR, UNIT_SEPARATOR};
+use parsing::{RECORD_SEPARATOR, TASKLIST_VERSION, UNIT_SEPARATOR};
 
 const TASKLIST_FILENAME: &str = ".ryattl";
+const CURRENT_TASK_EXTENSION: &str = "current";
+const TEMPORARY_EXTENSION: &str = "tmp";
+const LOCK_EXTENSION: &str = "lock";
+const BACKUP_EXTENSION: &str = "bak";
+const LOCK_TIMEOUT: time::Duration = time::Duration::from_secs(2);
+const LARGE_PRIORITY_MARGIN: usize = 1000;
+
+// Set once from '--quiet' before any command runs.
+static QUIET: atomic::AtomicBool = atomic::AtomicBool::new(false);
+// Set once from '--dry-run' before any command runs.
+static DRY_RUN: atomic::AtomicBool = atomic::AtomicBool::new(false);
+// Set when a query finds nothing and '--exit-code' was given.
+static NOTHING_FOUND: atomic::AtomicBool = atomic::AtomicBool::new(false);
 
 /// Yet Another Terminal-based Task List written in Rust
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Args {
+    /// Output format of the commands that display tasks
+    #[arg(long, global = true, value_enum, default_value_t = Format::Human)]
+    format: Format,
+
+    /// Disable colored output (also disabled by setting NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Don't print success messages and warnings
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// Exit with a failure status when a query finds no tasks, like grep
+    #[arg(short, long, global = true)]
+    exit_code: bool,
+
+    /// Show what a command would change without saving it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Use the task list in the home directory (also enabled by setting RYATTL_GLOBAL)
+    #[arg(short, long, global = true)]
+    global: bool,
+
+    /// Use the named task list (stored as '.ryattl.<NAME>') instead of the default one
+    #[arg(long, global = true, value_name = "NAME", value_parser = parsing::parse_list_name)]
+    list: Option<String>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -41,57 +85,501 @@ struct Args {
 enum Command {
     /// Add a task
     Add {
-        /// Priority associated with the task ('min', 'max' or a whole number)
-        #[arg(short, value_parser = parsing::parse_priority, default_value_t = Priority::Min)]
-        priority: Priority,
+        /// Priority associated with the task ('min', 'low', 'medium', 'high', 'max' or a whole number) [default: 'default_priority' from the config file, or 'min']
+        #[arg(short, value_parser = parsing::parse_priority)]
+        priority: Option<Priority>,
+
+        /// When the task is due (a date as YYYY-MM-DD or a duration like '12h', '3d' or '1w')
+        #[arg(long, value_parser = parsing::parse_due)]
+        due: Option<jiff::Zoned>,
+
+        /// Tag to label the task with (can be repeated)
+        #[arg(short, long = "tag", value_name = "TAG", value_parser = parsing::parse_tag)]
+        tags: Vec<String>,
+
+        /// Place the task right after the current task (the last one viewed with 'info', added, completed or modified)
+        #[arg(long, conflicts_with_all = ["priority", "before_current"])]
+        after_current: bool,
+
+        /// Place the task right before the current task (the last one viewed with 'info', added, completed or modified)
+        #[arg(long, conflicts_with = "priority")]
+        before_current: bool,
+
+        /// Add the task without asking for confirmation
+        #[arg(short, long)]
+        force: bool,
+
+        /// Add the task even if the task list already has one with the same message
+        #[arg(long)]
+        allow_duplicate: bool,
+
+        /// How similar (0 to 1) an existing message must be to be reported as a duplicate
+        #[arg(long, value_parser = parsing::parse_similarity, default_value_t = 0.8)]
+        similarity: f64,
+
+        /// Read the messages from stdin, one task per line (implies --force)
+        #[arg(long, conflicts_with_all = ["task", "after_current", "before_current"])]
+        batch: bool,
 
         /// Message associated with the task
-        task: String,
+        #[arg(required_unless_present = "batch", value_parser = parsing::parse_message)]
+        task: Option<String>,
     },
 
-    /// Display detailed information about a task
-    Info {
+    /// Add some text to the end of a task's message
+    Append {
+        /// Start the text on a new line instead of after a space
+        #[arg(long)]
+        newline: bool,
+
+        /// ID or UUID prefix associated with the task
+        #[arg(value_parser = parsing::parse_task_ref)]
+        task_id: TaskRef,
+
+        /// Text to append to the message
+        #[arg(value_parser = parsing::parse_message)]
+        text: String,
+    },
+
+    /// Move a task one place up or down by swapping it with its neighbour
+    Bump {
+        /// ID associated with the task
+        #[arg(value_parser = parsing::parse_task_id)]
+        task_id: usize,
+
+        /// Direction to move the task in
+        #[arg(value_enum)]
+        direction: Direction,
+    },
+
+    /// Validate the task list, reporting every record that can't be parsed
+    Check,
+
+    /// Remove all the tasks
+    Clear {
+        /// Clear the task list without asking for confirmation
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Generate a shell completion script
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate the completion script for
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the number of tasks
+    Count {
+        /// Include completed tasks
+        #[arg(short, long)]
+        all: bool,
+
+        /// Only count overdue tasks
+        #[arg(long)]
+        overdue: bool,
+
+        /// Only count tasks labeled with this tag (can be repeated)
+        #[arg(short, long = "tag", value_name = "TAG", value_parser = parsing::parse_tag)]
+        tags: Vec<String>,
+
+        /// Only count tasks with exactly this priority ('min', 'low', 'medium', 'high', 'max' or a whole number)
+        #[arg(short, long, value_parser = parsing::parse_priority)]
+        priority: Option<Priority>,
+    },
+
+    /// Mark a task as completed
+    Done {
+        /// ID associated with the task
+        #[arg(value_parser = parsing::parse_task_id)]
+        task_id: usize,
+    },
+
+    /// Edit the message of a task in a text editor ($EDITOR)
+    Edit {
         /// ID associated with the task
         #[arg(value_parser = parsing::parse_task_id)]
         task_id: usize,
     },
 
+    /// Export the task list to stdout
+    Export {
+        /// Format to export the task list in
+        #[arg(value_name = "FORMAT")]
+        export_format: ExportFormat,
+    },
+
+    /// Clean up the task list, dropping blank lines and records that can't be parsed
+    Gc,
+
+    /// Import tasks from a text file with one task per line ('-' for stdin)
+    Import {
+        /// Priority associated with the imported tasks ('min', 'low', 'medium', 'high', 'max' or a whole number)
+        #[arg(short, value_parser = parsing::parse_priority, default_value_t = Priority::Min)]
+        priority: Priority,
+
+        /// Read a JSON array of tasks ('message' and optionally 'priority', 'due' and 'tags') instead of lines
+        #[arg(long)]
+        json: bool,
+
+        /// Path to the file to import
+        path: path::PathBuf,
+    },
+
+    /// Display detailed information about a task
+    Info {
+        /// Print the task as it is stored in the task list file
+        #[arg(long)]
+        raw: bool,
+
+        /// Don't style the 'min' and 'max' priorities differently from numeric ones
+        #[arg(long)]
+        plain_priority: bool,
+
+        /// Show how long ago the task was created instead of the date
+        #[arg(short, long)]
+        relative: bool,
+
+        /// ID or UUID prefix associated with the task
+        #[arg(value_parser = parsing::parse_task_ref)]
+        task_id: TaskRef,
+    },
+
     /// Initiate a new task list in the current directory
     Init,
 
-    /// List all the tasks
-    List,
+    /// List the tasks
+    List {
+        /// Include completed tasks
+        #[arg(short, long)]
+        all: bool,
+
+        /// Show the creation dates of the tasks
+        #[arg(short, long)]
+        dates: bool,
+
+        /// Only show overdue tasks
+        #[arg(long)]
+        overdue: bool,
+
+        /// Only show tasks labeled with this tag (can be repeated)
+        #[arg(short, long = "tag", value_name = "TAG", value_parser = parsing::parse_tag)]
+        tags: Vec<String>,
+
+        /// Only show tasks with exactly this priority ('min', 'low', 'medium', 'high', 'max' or a whole number)
+        #[arg(short, long, alias = "priority-is", value_parser = parsing::parse_priority)]
+        priority: Option<Priority>,
+
+        /// Only show tasks with at least this priority ('min', 'low', 'medium', 'high', 'max' or a whole number)
+        #[arg(long, conflicts_with = "priority", value_parser = parsing::parse_priority)]
+        min_priority: Option<Priority>,
+
+        /// Only show tasks added since the given git commit
+        #[arg(long, value_name = "REF")]
+        since_commit: Option<String>,
+
+        /// Only show the given page of the (filtered) tasks
+        #[arg(long)]
+        page: Option<num::NonZeroUsize>,
+
+        /// Number of tasks per page
+        #[arg(long, default_value = "20")]
+        page_size: num::NonZeroUsize,
+
+        /// Show at most this many of the (filtered) tasks
+        #[arg(long, conflicts_with = "page")]
+        limit: Option<num::NonZeroUsize>,
+
+        /// Skip this many of the (filtered) tasks
+        #[arg(long, conflicts_with = "page", default_value_t = 0)]
+        offset: usize,
+
+        /// Only print the IDs of the tasks, one per line
+        #[arg(long)]
+        ids_only: bool,
+
+        /// Only show tasks created during the current calendar period
+        #[arg(long, value_enum)]
+        created_this: Option<Period>,
+
+        /// Only show tasks created at or after this date (YYYY-MM-DD) or duration ago (e.g. '7d ago')
+        #[arg(long, value_name = "DATE", value_parser = parsing::parse_date)]
+        created_after: Option<jiff::Zoned>,
+
+        /// Only show tasks created before this date (YYYY-MM-DD) or duration ago (e.g. '7d ago')
+        #[arg(long, value_name = "DATE", value_parser = parsing::parse_date)]
+        created_before: Option<jiff::Zoned>,
+
+        /// Summarize how old the (filtered) tasks are
+        #[arg(long)]
+        age_buckets: bool,
+
+        /// Comma-separated keys to sort by ('priority', 'created' or its alias 'age', or 'message'), each optionally suffixed with ':asc' or ':desc'; the tasks keep their IDs
+        #[arg(long, value_delimiter = ',', value_parser = parsing::parse_sort_key)]
+        sort: Vec<SortKey>,
+
+        /// Show the tasks in the opposite order, keeping their IDs
+        #[arg(short = 'R', long)]
+        reverse: bool,
+
+        /// Show the UUIDs of the tasks
+        #[arg(long)]
+        uuid: bool,
+
+        /// Wrap the messages to fit this many columns (defaults to the width of the terminal)
+        #[arg(long)]
+        width: Option<num::NonZeroUsize>,
+    },
 
     /// Modify a task
+    #[command(group(clap::ArgGroup::new("modifications").multiple(true)))]
     Modify {
-        /// Priority associated with the task ('min', 'max' or a whole number)
+        /// Priority associated with the task ('min', 'low', 'medium', 'high', 'max' or a whole number)
         #[arg(short, value_parser = parsing::parse_priority, group = "modifications")]
         priority: Option<Priority>,
 
+        /// Amount to raise (+N) or lower (-N) the numeric priority of the task by
+        #[arg(
+            long,
+            value_parser = parsing::parse_priority_offset,
+            group = "modifications",
+            conflicts_with = "priority",
+            allow_negative_numbers = true
+        )]
+        priority_relative: Option<isize>,
+
+        /// Message associated with the task
+        #[arg(short, long, group = "modifications", value_parser = parsing::parse_message)]
+        message: Option<String>,
+
+        /// ID or UUID prefix associated with the task
+        #[arg(value_parser = parsing::parse_task_ref, requires = "modifications")]
+        task_id: TaskRef,
+    },
+
+    /// Move a task to another position, taking over the priority of its new neighbour if needed
+    Move {
         /// ID associated with the task
-        #[arg(value_parser = parsing::parse_task_id, requires = "modifications")]
+        #[arg(value_parser = parsing::parse_task_id)]
         task_id: usize,
+
+        /// ID the task should have after the move
+        #[arg(value_parser = parsing::parse_task_id)]
+        to: usize,
+    },
+
+    /// Open the task list in a text editor ($EDITOR)
+    OpenList,
+
+    /// Assign priorities to all the tasks by comparing them pairwise
+    Prioritize,
+
+    /// Remove all the completed tasks
+    Purge {
+        /// Purge the task list without asking for confirmation
+        #[arg(short, long)]
+        force: bool,
     },
 
     /// Remove a task
     Remove {
+        /// Remove the tasks without asking for confirmation
+        #[arg(short, long)]
+        force: bool,
+
+        /// IDs or UUID prefixes associated with the tasks, or ranges of them like '3..5' (inclusive)
+        #[arg(value_parser = parsing::parse_task_ref_range, required = true)]
+        task_ids: Vec<ops::RangeInclusive<TaskRef>>,
+    },
+
+    /// Search for tasks whose message contains the query
+    Search {
+        /// Match the case of the query exactly
+        #[arg(long)]
+        case_sensitive: bool,
+
+        /// Only print the IDs of the tasks, one per line
+        #[arg(long)]
+        ids_only: bool,
+
+        /// Text to look for in the messages
+        query: String,
+    },
+
+    /// Summarize the task list
+    Stats,
+
+    /// Exchange the priorities (and so the positions) of two tasks
+    Swap {
         /// ID associated with the task
         #[arg(value_parser = parsing::parse_task_id)]
         task_id: usize,
+
+        /// ID associated with the task to swap it with
+        #[arg(value_parser = parsing::parse_task_id)]
+        other: usize,
+    },
+
+    /// Show the most important pending tasks
+    Top {
+        /// Number of tasks to show
+        #[arg(default_value = "1")]
+        n: num::NonZeroUsize,
     },
+
+    /// Browse and update the task list interactively
+    Tui,
+
+    /// Revert the last change made to the task list
+    Undo,
+}
+
+#[derive(Default, serde::Deserialize)]
+struct Config {
+    default_priority: Option<Priority>,
+    date_format: Option<DateFormat>,
+}
+
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DateFormat {
+    Short,
+    #[default]
+    Medium,
+    Long,
+    Iso,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Markdown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Format {
+    Human,
+    Json,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImportedTask {
+    message: String,
+    priority: Option<Priority>,
+    due: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Period {
+    Today,
+    Week,
+    Month,
+    Year,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Priority {
     Max,
     Min,
     Value(usize),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SortField {
+    Created,
+    Message,
+    Priority,
+}
+
+#[derive(Clone, Copy)]
+struct SortKey {
+    field: SortField,
+    descending: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredTasklist<T> {
+    version: u32,
+    tasks: Vec<T>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 struct Task {
     priority: Priority,
     message: String,
     created_on: jiff::Zoned,
+    #[serde(default)]
+    completed: bool,
+    // Only set while the task is completed.
+    #[serde(default)]
+    completed_on: Option<jiff::Zoned>,
+    #[serde(default)]
+    due: Option<jiff::Zoned>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    modified_on: Option<jiff::Zoned>,
+    // Nil for tasks stored before UUIDs existed, until get_tasklist derives one.
+    #[serde(default)]
+    uuid: uuid::Uuid,
+    // Breaks ties between equal priorities, it's higher for tasks inserted later.
+    // Zero for tasks stored before it existed, until get_tasklist numbers them.
+    #[serde(default)]
+    sequence: u64,
+}
+
+#[derive(Clone)]
+enum TaskRef {
+    Id(usize),
+    Uuid(String),
+}
+
+impl Command {
+    fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Command::Check
+                | Command::Count { .. }
+                | Command::Export { .. }
+                | Command::Info { .. }
+                | Command::List { .. }
+                | Command::Search { .. }
+                | Command::Stats
+                | Command::Top { .. }
+        )
+    }
+}
+
+impl Task {
+    fn new(message: String, priority: Priority) -> Self {
+        Self {
+            priority,
+            message,
+            created_on: jiff::Zoned::now(),
+            completed: false,
+            completed_on: None,
+            due: None,
+            tags: Vec::new(),
+            modified_on: None,
+            uuid: uuid::Uuid::new_v4(),
+            sequence: 0,
+        }
+    }
+
+    // Among equal priorities, tasks inserted later are stored first and thus
+    // shown last.
+    fn sort_key(&self) -> (Priority, cmp::Reverse<u64>) {
+        (self.priority, cmp::Reverse(self.sequence))
+    }
 }
 
 impl fmt::Display for Priority {
@@ -121,8 +609,51 @@ impl PartialOrd for Priority {
     }
 }
 
+impl serde::Serialize for Priority {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Max => serializer.serialize_str("max"),
+            Self::Min => serializer.serialize_str("min"),
+            Self::Value(n) => serializer.serialize_u64(*n as u64),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Priority {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Value(usize),
+            Name(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Value(n) => Ok(Self::Value(n)),
+            Repr::Name(name) => parsing::parse_priority(&name).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl SortKey {
+    fn compare(&self, task: &Task, other: &Task) -> cmp::Ordering {
+        let ordering = match self.field {
+            SortField::Created => task.created_on.cmp(&other.created_on),
+            SortField::Message => task.message.cmp(&other.message),
+            SortField::Priority => task.priority.cmp(&other.priority),
+        };
+
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
 fn main() -> process::ExitCode {
     match internal_main() {
+        Ok(()) if NOTHING_FOUND.load(atomic::Ordering::Relaxed) => process::ExitCode::FAILURE,
         Ok(()) => process::ExitCode::SUCCESS,
         Err(err) => {
             eprintln!("{} {}", "error:".red().bold(), err);
@@ -134,234 +665,1906 @@ fn main() -> process::ExitCode {
 fn internal_main() -> Result<(), String> {
     let args = Args::parse();
 
-    if let Command::Init = args.command {
-        let tasklist_path = env::current_dir()
-            .map_err(|err| err.to_string())?
-            .join(TASKLIST_FILENAME);
+    QUIET.store(args.quiet, atomic::Ordering::Relaxed);
+    DRY_RUN.store(args.dry_run, atomic::Ordering::Relaxed);
 
-        match tasklist_path.try_exists() {
-            Ok(true) => {
-                eprint!(
-                    "{} this directory already has a tasklist\nDo you wish to overwrite it? {} ",
-                    "warning:".yellow().bold(),
-                    "[y/N]:".cyan().bold(),
-                );
+    if colors_disabled(
+        args.no_color || args.format == Format::Json,
+        env::var_os("NO_COLOR"),
+        env::var_os("CLICOLOR_FORCE"),
+        io::stdout().is_terminal(),
+    ) {
+        colored::control::set_override(false);
+    }
 
-                io::stderr().flush().map_err(|err| err.to_string())?;
+    if let Command::Completions { shell } = args.command {
+        let mut buffer = Vec::new();
 
-                let mut buffer = String::with_capacity(1);
+        clap_complete::generate(
+            shell,
+            &mut Args::command(),
+            env!("CARGO_PKG_NAME"),
+            &mut buffer,
+        );
 
-                io::stdin()
-                    .read_line(&mut buffer)
-                    .map_err(|err| err.to_string())?;
+        return io::stdout()
+            .write_all(&buffer)
+            .map_err(|err| err.to_string());
+    }
 
-                let Some('y') = buffer.chars().next() else {
-                    return Ok(());
-                };
+    let global = args.global || env::var_os("RYATTL_GLOBAL").is_some_and(|value| !value.is_empty());
+
+    let tasklist_filename = get_tasklist_filename(args.list.as_deref());
+
+    // An explicitly chosen list takes precedence over RYATTL_FILE, which in
+    // turn takes precedence over searching for one.
+    let tasklist_file = if args.global || args.list.is_some() {
+        None
+    } else {
+        env::var_os("RYATTL_FILE")
+            .filter(|value| !value.is_empty())
+            .map(path::PathBuf::from)
+    };
+
+    // These commands write to the task list directly instead of saving it.
+    if args.dry_run
+        && matches!(
+            args.command,
+            Command::Gc | Command::Init | Command::OpenList | Command::Undo
+        )
+    {
+        return Err(format!(
+            "this command doesn't support '{}'",
+            "--dry-run".bold()
+        ));
+    }
+
+    if let Command::Init = args.command {
+        let tasklist_path = match &tasklist_file {
+            Some(tasklist_path) => tasklist_path.clone(),
+            None => if global {
+                get_home_dir()?
+            } else {
+                env::current_dir().map_err(|err| err.to_string())?
             }
-            Err(err) => return Err(err.to_string()),
-            _ => (),
+            .join(&tasklist_filename),
+        };
+
+        if tasklist_path.try_exists().map_err(|err| err.to_string())?
+            && !confirm(&format!(
+                "{} this directory already has a tasklist\nDo you wish to overwrite it?",
+                "warning:".yellow().bold(),
+            ))?
+        {
+            return Ok(());
         }
 
-        fs::File::create(tasklist_path).map_err(|err| err.to_string())?;
-
-        println!(
-            "{} a new tasklist in the current directory",
-            "Initiated".green().bold()
-        );
+        fs::File::create(&tasklist_path).map_err(|err| err.to_string())?;
+
+        match tasklist_file {
+            Some(_) => print_success(
+                "Initiated",
+                &format!("a new tasklist at '{}'", tasklist_path.display()),
+            ),
+            None => print_success(
+                "Initiated",
+                &format!(
+                    "a new tasklist in the {} directory",
+                    if global { "home" } else { "current" },
+                ),
+            ),
+        }
 
         return Ok(());
     }
 
-    let tasklist_path = get_tasklist_path()?;
+    let tasklist_path = match tasklist_file {
+        Some(tasklist_path) if tasklist_path.is_file() => tasklist_path,
+        Some(tasklist_path) => {
+            return Err(format!(
+                "there is no task list at '{}' (set by RYATTL_FILE), create one with 'init'",
+                tasklist_path.display(),
+            ))
+        }
+        None => get_tasklist_path(global, &tasklist_filename)?,
+    };
+
+    // Held until internal_main returns, so the whole read-modify-write cycle
+    // is protected and the lock is released on every exit path. Commands that
+    // don't save neither need it nor wait for it.
+    let read_only = args.command.is_read_only();
+    let _lock = if read_only || args.dry_run {
+        None
+    } else {
+        Some(lock_tasklist(&tasklist_path)?)
+    };
+
+    match args.command {
+        Command::Check => return check_tasklist(&tasklist_path),
+        Command::Gc => return collect_garbage(tasklist_path),
+        Command::OpenList => return edit_tasklist(&tasklist_path),
+        Command::Undo => {
+            let backup_path = get_sibling_path(&tasklist_path, BACKUP_EXTENSION);
+
+            if !backup_path.try_exists().map_err(|err| err.to_string())? {
+                return Err("there is nothing to undo".to_owned());
+            }
+
+            // Moving the backup over the task list also consumes it, so the
+            // same change can't be undone twice.
+            fs::rename(backup_path, tasklist_path).map_err(|err| err.to_string())?;
+            print_success("Reverted", "the last change to the task list");
+
+            return Ok(());
+        }
+        _ => (),
+    }
+
     let mut tasklist = get_tasklist(&tasklist_path)?;
 
+    let config = get_config()?;
+    let date_format = match env::var("RYATTL_DATE_FORMAT") {
+        Ok(value) if !value.is_empty() => parsing::parse_date_format(&value)
+            .map_err(|err| format!("invalid value for RYATTL_DATE_FORMAT: {}", err))?,
+        _ => config.date_format.unwrap_or_default(),
+    };
+
     match args.command {
         Command::Add {
             priority,
+            due,
+            mut tags,
+            after_current,
+            before_current,
+            force,
+            allow_duplicate,
+            similarity,
+            batch,
             task: message,
         } => {
-            let task = Task {
-                message: message
-                    .chars()
-                    .map(|c| match c {
-                        RECORD_SEPARATOR | UNIT_SEPARATOR => ' ',
-                        c => c,
-                    })
-                    .collect(),
-                priority,
-                created_on: jiff::Zoned::now(),
+            let priority = priority
+                .or(config.default_priority)
+                .unwrap_or(Priority::Min);
+            let force = force || batch;
+
+            tags.sort();
+            tags.dedup();
+            let messages = match message {
+                Some(message) => vec![message],
+                None => io::stdin()
+                    .lines()
+                    .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| err.to_string())?,
             };
 
-            let mut begin = 0;
-            let mut end = tasklist.len();
+            // Without any numeric priority there's nothing to be far above.
+            let highest = tasklist
+                .iter()
+                .filter_map(|task| match task.priority {
+                    Priority::Value(value) => Some(value),
+                    _ => None,
+                })
+                .max();
+
+            if let (Priority::Value(value), Some(highest)) = (priority, highest) {
+                if !force
+                    && value.saturating_sub(highest) > LARGE_PRIORITY_MARGIN
+                    && !confirm(&format!(
+                        "{} the priority {} is far above the highest numeric priority ({}), did you mean '{}'?\nDo you wish to add it anyway?",
+                        "warning:".yellow().bold(),
+                        value.to_string().yellow(),
+                        highest,
+                        "max".bold(),
+                    ))?
+                {
+                    return Err("the task was not added".to_owned());
+                }
+            }
 
-            while begin < end {
-                let pivot = (begin + end) / 2;
-                match tasklist[pivot].priority.cmp(&task.priority) {
-                    cmp::Ordering::Less => {
-                        begin = pivot + 1;
+            let mut added = None;
+
+            for message in messages {
+                let mut task = Task {
+                    due: due.clone(),
+                    tags: tags.clone(),
+                    ..Task::new(sanitize_message(&message), priority)
+                };
+
+                if !force {
+                    let tasklist_len = tasklist.len();
+                    let normalized = task.message.trim().to_lowercase();
+                    let is_duplicate =
+                        |other: &Task| other.message.trim().to_lowercase() == normalized;
+
+                    if !allow_duplicate {
+                        if let Some((index, other)) = tasklist
+                            .iter()
+                            .enumerate()
+                            .rev()
+                            .find(|(_, other)| is_duplicate(other))
+                        {
+                            if !confirm(&format!(
+                                "{} the task list already has this task\n{}Do you wish to add it anyway?",
+                                "warning:".yellow().bold(),
+                                format_list_rows(
+                                    &[(tasklist_len - index - 1, other)],
+                                    tasklist_len,
+                                    None,
+                                    false,
+                                    None,
+                                )?,
+                            ))? {
+                                return Err("the task was not added".to_owned());
+                            }
+                        }
+                    }
+
+                    // Duplicates were already dealt with above.
+                    let similar: Vec<_> = tasklist
+                        .iter()
+                        .rev()
+                        .enumerate()
+                        .filter(|(_, other)| {
+                            !is_duplicate(other)
+                                && get_similarity(&task.message, &other.message) >= similarity
+                        })
+                        .collect();
+
+                    if !similar.is_empty()
+                        && !confirm(&format!(
+                            "{} the task list already has similar tasks\n{}Do you wish to add it anyway?",
+                            "warning:".yellow().bold(),
+                            format_list_rows(&similar, tasklist_len, None, false, None)?,
+                        ))?
+                    {
+                        return Err("the task was not added".to_owned());
                     }
-                    cmp::Ordering::Equal | cmp::Ordering::Greater => end = pivot,
                 }
+
+                let current_task_index = (after_current || before_current)
+                    .then(|| get_current_task_index(&tasklist_path, &tasklist))
+                    .transpose()?;
+
+                added = Some((added.map_or(0, |(count, _)| count) + 1, task.uuid));
+
+                if let Some(index) = current_task_index {
+                    task.priority = tasklist[index].priority;
+
+                    // The list is displayed in reverse, so a task is shown right
+                    // after the one above it in storage.
+                    tasklist.insert(if after_current { index } else { index + 1 }, task);
+                    renumber_tasks(&mut tasklist);
+                } else {
+                    insert_task(&mut tasklist, task);
+                }
+            }
+
+            let Some((added, uuid)) = added else {
+                return Ok(());
+            };
+
+            // The last task added becomes the current one, so further tasks
+            // added with '--after-current' follow it.
+            set_current_task(&tasklist_path, &uuid)?;
+
+            match added {
+                1 => print_success("Added", "a new task"),
+                n => print_success("Added", &format!("{} new tasks", n)),
+            }
+        }
+
+        Command::Append {
+            newline,
+            task_id,
+            text,
+        } => {
+            let task_id = resolve_task_ref(&task_id, &tasklist)?;
+            let tasklist_len = tasklist.len();
+
+            if task_id > tasklist_len {
+                return Err(build_invalid_task_id_error(task_id, tasklist_len));
             }
 
-            let pivot = (begin + end) / 2;
-            tasklist.insert(pivot, task);
+            let text = sanitize_message(&text);
 
-            println!("{} a new task", "Added".green().bold());
+            // The priority is unchanged, so the task keeps its position.
+            let task = &mut tasklist[tasklist_len - task_id];
+            task.message
+                .push(if newline { RECORD_SEPARATOR } else { ' ' });
+            task.message.push_str(text.trim());
+            task.modified_on = Some(jiff::Zoned::now());
+
+            print_success("Appended", "to the specified task");
         }
 
-        Command::Info { task_id } => {
+        Command::Bump { task_id, direction } => {
             let tasklist_len = tasklist.len();
 
             if task_id > tasklist_len {
                 return Err(build_invalid_task_id_error(task_id, tasklist_len));
             }
 
-            let task = unsafe { tasklist.get_unchecked(tasklist_len - task_id) };
-            let created_on = {
-                let created_on = task
-                    .created_on
-                    .with_time_zone(tz::TimeZone::system())
-                    .datetime();
-
-                // Create ICU datetime.
-                let datetime = icu_calendar::DateTime::try_new_iso_datetime(
-                    i32::from(created_on.year()),
-                    // These unwraps are all guaranteed to be
-                    // correct because Jiff's bounds on allowable
-                    // values fit within icu's bounds.
-                    u8::try_from(created_on.month()).unwrap(),
-                    u8::try_from(created_on.day()).unwrap(),
-                    u8::try_from(created_on.hour()).unwrap(),
-                    u8::try_from(created_on.minute()).unwrap(),
-                    u8::try_from(created_on.second()).unwrap(),
-                ).unwrap();
-
-                icu_calendar::DateTime::new_from_iso(datetime, icu_calendar::Gregorian)
+            let new_task_id = match direction {
+                Direction::Up if task_id == 1 => {
+                    eprintln!("The specified task is already at the top");
+                    return Ok(());
+                }
+                Direction::Down if task_id == tasklist_len => {
+                    eprintln!("The specified task is already at the bottom");
+                    return Ok(());
+                }
+                Direction::Up => task_id - 1,
+                Direction::Down => task_id + 1,
             };
 
-            let locale = sys_locale::get_locale()
-                .and_then(|locale_string| locale_string.parse::<icu_locid::Locale>().ok())
-                .unwrap_or(locale!("en"));
-            let formatter = icu_datetime::TypedDateTimeFormatter::try_new(
-                &locale.clone().into(),
-                Default::default(),
-            )
-            .map_err(|err| err.to_string())?;
+            swap_tasks(
+                &mut tasklist,
+                tasklist_len - task_id,
+                tasklist_len - new_task_id,
+            );
+
+            print_success(
+                "Bumped",
+                &format!(
+                    "the specified task, its ID is now {}",
+                    new_task_id.to_string().yellow(),
+                ),
+            );
+        }
 
-            println!(
-                " {:<width$} {}\n {:<width$} {}\n {:<width$} {}\n {:<width$} {}",
-                "ID:".bold(),
-                task_id.to_string().yellow(),
-                "Priority:".bold(),
-                task.priority.to_string().cyan(),
-                "Message:".bold(),
-                task.message.green(),
-                "Date:".bold(),
-                formatter.format(&created_on).to_string().blue(),
-                width = 10,
-            )
+        Command::Count {
+            all,
+            overdue,
+            tags,
+            priority,
+        } => {
+            let now = jiff::Zoned::now();
+            let count = tasklist
+                .iter()
+                .filter(|task| all || !task.completed)
+                .filter(|task| !overdue || is_overdue(task, &now))
+                .filter(|task| tags.iter().all(|tag| task.tags.contains(tag)))
+                .filter(|task| priority.is_none_or(|priority| task.priority == priority))
+                .count();
+
+            if count == 0 {
+                NOTHING_FOUND.store(args.exit_code, atomic::Ordering::Relaxed);
+            }
+
+            println!("{}", count);
+            return Ok(());
         }
 
-        Command::List => {
+        Command::Clear { force } => {
             if tasklist.is_empty() {
-                eprintln!("The task list is empty");
+                eprintln!("Nothing to clear");
                 return Ok(());
             }
 
-            let mut buffer = String::new();
-
-            for (index, task) in tasklist.iter().rev().enumerate() {
-                buffer.push_str(&format!(
-                    " {:^width$} | {}\n",
-                    (index + 1).to_string().yellow(),
-                    task.message.green(),
-                    width = tasklist.len().ilog10() as usize + 1,
-                ));
+            if !force
+                && !args.dry_run
+                && !confirm(&format!(
+                    "{} all {} tasks will be removed\nDo you wish to continue?",
+                    "warning:".yellow().bold(),
+                    tasklist.len(),
+                ))?
+            {
+                return Ok(());
             }
 
-            print!("{}", buffer);
-
-            return Ok(());
+            print_success("Cleared", &format!("{} tasks", tasklist.len()));
+            tasklist.clear();
         }
 
-        Command::Modify { priority, task_id } => {
+        Command::Done { task_id } => {
             let tasklist_len = tasklist.len();
 
             if task_id > tasklist_len {
                 return Err(build_invalid_task_id_error(task_id, tasklist_len));
             }
 
-            let task = unsafe { tasklist.get_unchecked_mut(tasklist_len - task_id) };
-            let is_sorted = priority.is_none();
+            let task = &mut tasklist[tasklist_len - task_id];
 
-            if let Some(priority) = priority {
-                task.priority = priority;
+            if task.completed {
+                eprintln!("The specified task is already completed");
+                return Ok(());
             }
 
-            println!("{} the specified task", "Modified".green().bold());
-
-            if !is_sorted {
-                tasklist.sort_by_key(|task| task.priority);
-                eprintln!(
-                    "{} the priority was changed and as a result the task IDs might have also changed",
-                    "warning:".yellow().bold(),
-                );
-            }
+            task.completed = true;
+            task.completed_on = Some(jiff::Zoned::now());
+            set_current_task(&tasklist_path, &task.uuid)?;
+            print_success("Completed", "the specified task");
         }
 
-        Command::Remove { task_id } => {
+        Command::Edit { task_id } => {
             let tasklist_len = tasklist.len();
 
             if task_id > tasklist_len {
                 return Err(build_invalid_task_id_error(task_id, tasklist_len));
             }
 
-            tasklist.remove(tasklist_len - task_id);
-            println!("{} the specified task", "Removed".green().bold());
-        }
+            let task = &mut tasklist[tasklist_len - task_id];
+            let message = edit_message(&task.message)?;
 
-        _ => unreachable!(),
-    }
+            if message.trim().is_empty() {
+                return Err("the message is empty, the task was left unchanged".to_owned());
+            }
 
-    save_tasklist(tasklist_path, tasklist)
-}
+            task.message = sanitize_message(message.trim_end_matches(['\r', '\n']));
+            task.modified_on = Some(jiff::Zoned::now());
+            print_success("Edited", "the specified task");
+        }
 
-fn get_tasklist(tasklist_path: &path::Path) -> Result<Vec<Task>, String> {
-    let tasklist: Result<Vec<Task>, _> = fs::read_to_string(tasklist_path)
-        .map_err(|err| err.to_string())?
-        .lines()
-        .map(parsing::parse_task)
-        .collect();
+        Command::Export { export_format } => {
+            let tasklist_len = tasklist.len();
+            let mut buffer = match export_format {
+                ExportFormat::Csv => "id,priority,message,created_on\n".to_owned(),
+                ExportFormat::Markdown => String::new(),
+            };
+
+            for (index, task) in tasklist.iter().enumerate().rev() {
+                let task_id = tasklist_len - index;
+
+                match export_format {
+                    ExportFormat::Csv => buffer.push_str(&format!(
+                        "{},{},\"{}\",{}\n",
+                        task_id,
+                        task.priority,
+                        task.message.replace('"', "\"\""),
+                        format_rfc3339(&task.created_on),
+                    )),
+                    ExportFormat::Markdown => buffer.push_str(&format!(
+                        "- [{}] {} (priority: {})\n",
+                        if task.completed { 'x' } else { ' ' },
+                        escape_newlines(&task.message),
+                        task.priority,
+                    )),
+                }
+            }
+
+            io::stdout()
+                .write_all(buffer.as_bytes())
+                .map_err(|err| err.to_string())?;
+
+            return Ok(());
+        }
+
+        Command::Import {
+            priority,
+            json,
+            path,
+        } => {
+            let contents = if path.as_os_str() == "-" {
+                io::read_to_string(io::stdin())
+            } else {
+                fs::read_to_string(&path)
+            }
+            .map_err(|err| format!("couldn't read '{}': {}", path.display(), err))?;
+
+            let tasks = if json {
+                let entries: Vec<serde_json::Value> = serde_json::from_str(&contents)
+                    .map_err(|err| format!("expected a JSON array of tasks: {}", err))?;
+
+                // Everything is validated before anything is added.
+                entries
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, entry)| {
+                        parse_imported_task(entry, priority)
+                            .map_err(|err| format!("invalid task at index {}: {}", index, err))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?
+            } else {
+                contents
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| Task::new(sanitize_message(line), priority))
+                    .collect()
+            };
+
+            let imported = tasks.len();
+
+            for task in tasks {
+                insert_task(&mut tasklist, task);
+            }
+
+            print_success("Imported", &format!("{} tasks", imported));
+        }
+
+        Command::Info {
+            raw,
+            plain_priority,
+            relative,
+            task_id,
+        } => {
+            let task_id = resolve_task_ref(&task_id, &tasklist)?;
+            let tasklist_len = tasklist.len();
+
+            if task_id > tasklist_len {
+                return Err(build_invalid_task_id_error(task_id, tasklist_len));
+            }
+
+            let task = &tasklist[tasklist_len - task_id];
+
+            set_current_task(&tasklist_path, &task.uuid)?;
+
+            if raw {
+                println!(
+                    "{}",
+                    serde_json::to_string(task).map_err(|err| err.to_string())?
+                );
+
+                return Ok(());
+            }
+
+            if args.format == Format::Json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&format_task_json(task_id, task))
+                        .map_err(|err| err.to_string())?
+                );
+
+                return Ok(());
+            }
+
+            let mut rows = vec![
+                ("ID:", task_id.to_string().yellow().to_string()),
+                ("UUID:", task.uuid.to_string().dimmed().to_string()),
+                (
+                    "Priority:",
+                    colorize_priority(&task.priority, plain_priority).to_string(),
+                ),
+                (
+                    "Rank:",
+                    format!(
+                        "{} of {} (top {}%)",
+                        task_id,
+                        tasklist_len,
+                        (task_id * 100 + tasklist_len / 2) / tasklist_len,
+                    )
+                    .cyan()
+                    .to_string(),
+                ),
+                ("Message:", task.message.green().to_string()),
+                (
+                    "Date:",
+                    if relative {
+                        let span = task
+                            .created_on
+                            .until((jiff::Unit::Year, &jiff::Zoned::now()))
+                            .map_err(|err| err.to_string())?;
+
+                        humanize_span(span)
+                    } else {
+                        format_created_on(&task.created_on, date_format)?
+                    }
+                    .blue()
+                    .to_string(),
+                ),
+            ];
+
+            if let Some(modified_on) = &task.modified_on {
+                rows.push((
+                    "Modified:",
+                    format_created_on(modified_on, date_format)?
+                        .blue()
+                        .to_string(),
+                ));
+            }
+
+            if let Some(due) = &task.due {
+                let mut value = format_created_on(due, date_format)?.blue().to_string();
+
+                if is_overdue(task, &jiff::Zoned::now()) {
+                    value.push_str(&" (overdue)".red().bold().to_string());
+                }
+
+                rows.push(("Due:", value));
+            }
+
+            if !task.tags.is_empty() {
+                rows.push(("Tags:", task.tags.join(", ").magenta().to_string()));
+            }
+
+            let mut completed = if task.completed { "yes" } else { "no" }.cyan().to_string();
+
+            // Tasks completed before the date was recorded only have the flag.
+            if let Some(completed_on) = &task.completed_on {
+                completed.push_str(&format!(
+                    " ({})",
+                    format_created_on(completed_on, date_format)?.blue()
+                ));
+            }
+
+            rows.push(("Completed:", completed));
+
+            print_rows(rows);
+        }
+
+        Command::List {
+            all,
+            dates,
+            overdue,
+            tags,
+            priority,
+            min_priority,
+            since_commit,
+            page,
+            page_size,
+            limit,
+            offset,
+            ids_only,
+            created_this,
+            created_after,
+            created_before,
+            age_buckets,
+            sort,
+            reverse,
+            uuid,
+            width,
+        } => {
+            if tasklist.is_empty() && args.format == Format::Human {
+                NOTHING_FOUND.store(args.exit_code, atomic::Ordering::Relaxed);
+                eprintln!("The task list is empty");
+                return Ok(());
+            }
+
+            let committed = match since_commit {
+                Some(reference) => get_committed_tasks(&tasklist_path, &reference)?,
+                None => None,
+            };
+
+            let period_start = created_this.map(get_period_start).transpose()?;
+            let now = jiff::Zoned::now();
+
+            let mut tasks: Vec<_> = tasklist
+                .iter()
+                .rev()
+                .enumerate()
+                .filter(|(_, task)| all || !task.completed)
+                .filter(|(_, task)| !overdue || is_overdue(task, &now))
+                .filter(|(_, task)| tags.iter().all(|tag| task.tags.contains(tag)))
+                .filter(|(_, task)| priority.is_none_or(|priority| task.priority == priority))
+                .filter(|(_, task)| min_priority.is_none_or(|priority| task.priority >= priority))
+                .filter(|(_, task)| {
+                    committed
+                        .as_ref()
+                        .is_none_or(|committed| !committed.contains(&task.uuid))
+                })
+                .filter(|(_, task)| {
+                    period_start
+                        .as_ref()
+                        .is_none_or(|period_start| task.created_on >= *period_start)
+                })
+                .filter(|(_, task)| {
+                    created_after
+                        .as_ref()
+                        .is_none_or(|created_after| task.created_on >= *created_after)
+                })
+                .filter(|(_, task)| {
+                    created_before
+                        .as_ref()
+                        .is_none_or(|created_before| task.created_on < *created_before)
+                })
+                .collect();
+
+            if tasks.is_empty() {
+                NOTHING_FOUND.store(args.exit_code, atomic::Ordering::Relaxed);
+            }
+
+            if tasks.is_empty() && args.format == Format::Human {
+                eprintln!("No tasks match the given filters");
+                return Ok(());
+            }
+
+            tasks.sort_by(|(_, task), (_, other)| {
+                sort.iter()
+                    .map(|key| key.compare(task, other))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(cmp::Ordering::Equal)
+            });
+
+            if reverse {
+                tasks.reverse();
+            }
+
+            let age_buckets = age_buckets.then(get_now).transpose()?.map(|now| {
+                let now = now.timestamp();
+                let mut buckets = [0; 4];
+
+                for (_, task) in tasks.iter().filter(|(_, task)| !task.completed) {
+                    let days = (now.as_second() - task.created_on.timestamp().as_second()) / 86400;
+
+                    buckets[match days {
+                        ..1 => 0,
+                        1..7 => 1,
+                        7..28 => 2,
+                        _ => 3,
+                    }] += 1;
+                }
+
+                buckets
+            });
+
+            let tasks_len = tasks.len();
+            let footer = page.map(|page| {
+                let page_count = tasks.len().div_ceil(page_size.get());
+
+                tasks = tasks
+                    .drain(..)
+                    .skip((page.get() - 1).saturating_mul(page_size.get()))
+                    .take(page_size.get())
+                    .collect();
+
+                format!("page {} of {}", page, page_count)
+            });
+
+            let footer = if limit.is_some() || offset > 0 {
+                tasks = tasks
+                    .drain(..)
+                    .skip(offset)
+                    .take(limit.map_or(usize::MAX, num::NonZeroUsize::get))
+                    .collect();
+
+                Some(match tasks.len() {
+                    0 => format!("showing none of {}", tasks_len),
+                    len => format!("showing {}–{} of {}", offset + 1, offset + len, tasks_len),
+                })
+            } else {
+                footer
+            };
+
+            if args.format == Format::Json {
+                let tasks: Vec<_> = tasks
+                    .into_iter()
+                    .map(|(index, task)| format_task_json(index + 1, task))
+                    .collect();
+
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&tasks).map_err(|err| err.to_string())?
+                );
+
+                if let Some(footer) = footer {
+                    eprintln!("{}", footer);
+                }
+
+                return Ok(());
+            }
+
+            let mut buffer = if ids_only {
+                tasks
+                    .iter()
+                    .map(|(index, _)| format!("{}\n", index + 1))
+                    .collect()
+            } else {
+                format_list_rows(
+                    &tasks,
+                    tasklist.len(),
+                    dates.then_some(date_format),
+                    uuid,
+                    width,
+                )?
+            };
+
+            if let Some(buckets) = age_buckets {
+                buffer.push_str(&format!(
+                    "\n {:<width$} {}\n {:<width$} {}\n {:<width$} {}\n {:<width$} {}\n",
+                    "< 1 day:".bold(),
+                    buckets[0].to_string().yellow(),
+                    "1-7 days:".bold(),
+                    buckets[1].to_string().yellow(),
+                    "1-4 weeks:".bold(),
+                    buckets[2].to_string().yellow(),
+                    "> 4 weeks:".bold(),
+                    buckets[3].to_string().yellow(),
+                    width = 10,
+                ));
+            }
+
+            print!("{}", buffer);
+
+            if let Some(footer) = footer {
+                eprintln!("{}", footer);
+            }
+
+            return Ok(());
+        }
+
+        Command::Modify {
+            priority,
+            priority_relative,
+            message,
+            task_id,
+        } => {
+            let task_id = resolve_task_ref(&task_id, &tasklist)?;
+            let tasklist_len = tasklist.len();
+
+            if task_id > tasklist_len {
+                return Err(build_invalid_task_id_error(task_id, tasklist_len));
+            }
+
+            let task = &mut tasklist[tasklist_len - task_id];
+            let is_sorted = priority.is_none() && priority_relative.is_none();
+
+            if let Some(priority) = priority {
+                task.priority = priority;
+            }
+
+            if let Some(offset) = priority_relative {
+                let Priority::Value(value) = task.priority else {
+                    return Err(format!(
+                        "the task has the '{}' priority, only numeric priorities can be changed relatively",
+                        task.priority.to_string().yellow(),
+                    ));
+                };
+
+                task.priority = Priority::Value(value.saturating_add_signed(offset));
+            }
+
+            if let Some(message) = message {
+                task.message = sanitize_message(&message);
+            }
+
+            task.modified_on = Some(jiff::Zoned::now());
+            set_current_task(&tasklist_path, &task.uuid)?;
+            print_success("Modified", "the specified task");
+
+            // Re-inserted like a new task, it's shown after the existing tasks of
+            // its new priority instead of wherever a re-sort would leave it.
+            if !is_sorted {
+                let task = tasklist.remove(tasklist_len - task_id);
+                insert_task(&mut tasklist, task);
+                print_warning(
+                    "the priority was changed and as a result the task IDs might have also changed",
+                );
+            }
+        }
+
+        Command::Move { task_id, to } => {
+            let tasklist_len = tasklist.len();
+
+            if let Some(task_id) = [task_id, to].into_iter().find(|&id| id > tasklist_len) {
+                return Err(build_invalid_task_id_error(task_id, tasklist_len));
+            }
+
+            if task_id == to {
+                eprintln!("The specified task is already at that position");
+                return Ok(());
+            }
+
+            let mut task = tasklist.remove(tasklist_len - task_id);
+            let index = tasklist_len - to;
+            let priority = task.priority;
+
+            // The priority only changes when it doesn't fit between the new
+            // neighbours, ties are then kept in place by the sequence numbers.
+            if let Some(below) = index.checked_sub(1).map(|index| tasklist[index].priority) {
+                task.priority = task.priority.max(below);
+            }
+
+            if let Some(above) = tasklist.get(index).map(|task| task.priority) {
+                task.priority = task.priority.min(above);
+            }
+
+            let changed = task.priority != priority;
+
+            tasklist.insert(index, task);
+            renumber_tasks(&mut tasklist);
+
+            print_success("Moved", "the specified task");
+
+            if changed {
+                print_warning("the task took over the priority of its new neighbour");
+            }
+        }
+
+        Command::Purge { force } => {
+            let completed = tasklist.iter().filter(|task| task.completed).count();
+
+            if completed == 0 {
+                eprintln!("Nothing to purge");
+                return Ok(());
+            }
+
+            if !force
+                && !args.dry_run
+                && !confirm(&format!(
+                    "{} {} completed tasks will be removed\nDo you wish to continue?",
+                    "warning:".yellow().bold(),
+                    completed,
+                ))?
+            {
+                return Ok(());
+            }
+
+            tasklist.retain(|task| !task.completed);
+            print_success("Purged", &format!("{} tasks", completed));
+        }
+
+        Command::Prioritize => {
+            if tasklist.is_empty() {
+                eprintln!("The task list is empty");
+                return Ok(());
+            }
+
+            let mut ranking: Vec<Task> = Vec::with_capacity(tasklist.len());
+
+            for task in tasklist.into_iter().rev() {
+                let mut begin = 0;
+                let mut end = ranking.len();
+
+                while begin < end {
+                    let pivot = (begin + end) / 2;
+
+                    if ask_more_important(&task, &ranking[pivot])? {
+                        begin = pivot + 1;
+                    } else {
+                        end = pivot;
+                    }
+                }
+
+                ranking.insert(begin, task);
+            }
+
+            for (index, task) in ranking.iter_mut().enumerate() {
+                task.priority = Priority::Value(index + 1);
+            }
+
+            tasklist = ranking;
+
+            print_success("Prioritized", "the tasks");
+            print_warning(
+                "the priorities were changed and as a result the task IDs might have also changed",
+            );
+        }
+
+        Command::Stats => {
+            let completed = tasklist.iter().filter(|task| task.completed).count();
+            let max = tasklist
+                .iter()
+                .filter(|task| task.priority == Priority::Max)
+                .count();
+            let min = tasklist
+                .iter()
+                .filter(|task| task.priority == Priority::Min)
+                .count();
+            let numeric = tasklist.len() - max - min;
+            let oldest = tasklist.iter().map(|task| &task.created_on).min();
+            let newest = tasklist.iter().map(|task| &task.created_on).max();
+
+            if args.format == Format::Json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&serde_json::json!({
+                        "total": tasklist.len(),
+                        "completed": completed,
+                        "priorities": {
+                            "max": max,
+                            "numeric": numeric,
+                            "min": min,
+                        },
+                        "oldest": oldest.map(format_rfc3339),
+                        "newest": newest.map(format_rfc3339),
+                    }))
+                    .map_err(|err| err.to_string())?
+                );
+
+                return Ok(());
+            }
+
+            let mut rows = vec![
+                ("Total:", tasklist.len().to_string().yellow().to_string()),
+                ("Completed:", completed.to_string().yellow().to_string()),
+                ("Max:", max.to_string().cyan().to_string()),
+                ("Numeric:", numeric.to_string().cyan().to_string()),
+                ("Min:", min.to_string().cyan().to_string()),
+            ];
+
+            if let (Some(oldest), Some(newest)) = (oldest, newest) {
+                rows.push((
+                    "Oldest:",
+                    format_created_on(oldest, date_format)?.blue().to_string(),
+                ));
+                rows.push((
+                    "Newest:",
+                    format_created_on(newest, date_format)?.blue().to_string(),
+                ));
+            }
+
+            print_rows(rows);
+
+            return Ok(());
+        }
+
+        Command::Swap { task_id, other } => {
+            let tasklist_len = tasklist.len();
+
+            if let Some(task_id) = [task_id, other].into_iter().find(|&id| id > tasklist_len) {
+                return Err(build_invalid_task_id_error(task_id, tasklist_len));
+            }
+
+            if task_id == other {
+                return Err("a task can't be swapped with itself".to_owned());
+            }
+
+            swap_tasks(&mut tasklist, tasklist_len - task_id, tasklist_len - other);
+
+            print_success("Swapped", "the specified tasks");
+            print_warning("the tasks exchanged their IDs along with their priorities");
+        }
+
+        Command::Search {
+            case_sensitive,
+            ids_only,
+            query,
+        } => {
+            let query = if case_sensitive {
+                query
+            } else {
+                query.to_lowercase()
+            };
+
+            let tasks: Vec<_> = tasklist
+                .iter()
+                .rev()
+                .enumerate()
+                .filter(|(_, task)| {
+                    if case_sensitive {
+                        task.message.contains(&query)
+                    } else {
+                        task.message.to_lowercase().contains(&query)
+                    }
+                })
+                .collect();
+
+            if tasks.is_empty() {
+                NOTHING_FOUND.store(args.exit_code, atomic::Ordering::Relaxed);
+                eprintln!("No matching tasks");
+                return Ok(());
+            }
+
+            if ids_only {
+                for (index, _) in tasks {
+                    println!("{}", index + 1);
+                }
+            } else {
+                print!(
+                    "{}",
+                    format_list_rows(&tasks, tasklist.len(), None, false, None)?
+                );
+            }
+
+            return Ok(());
+        }
+
+        Command::Remove { force, task_ids } => {
+            let tasklist_len = tasklist.len();
+            let task_ids = task_ids
+                .iter()
+                .map(|range| {
+                    let start = resolve_task_ref(range.start(), &tasklist)?;
+                    let end = resolve_task_ref(range.end(), &tasklist)?;
+
+                    if start > end {
+                        return Err(
+                            "expected the start of the range to not be greater than its end"
+                                .to_owned(),
+                        );
+                    }
+
+                    Ok(start..=end)
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+
+            // Validate every range before expanding any of them.
+            if let Some(range) = task_ids.iter().find(|range| *range.end() > tasklist_len) {
+                let task_id = if *range.start() > tasklist_len {
+                    *range.start()
+                } else {
+                    tasklist_len + 1
+                };
+
+                return Err(build_invalid_task_id_error(task_id, tasklist_len));
+            }
+
+            let mut indices: Vec<usize> = task_ids
+                .into_iter()
+                .flatten()
+                .map(|task_id| tasklist_len - task_id)
+                .collect();
+
+            indices.sort_unstable();
+
+            if let Some(index) = indices.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(format!(
+                    "the task ID '{}' was given more than once",
+                    (tasklist_len - index[0]).to_string().yellow(),
+                ));
+            }
+
+            let tasks: Vec<_> = indices
+                .iter()
+                .rev()
+                .map(|&index| (tasklist_len - index - 1, &tasklist[index]))
+                .collect();
+
+            if args.dry_run {
+                print!(
+                    "{}",
+                    format_list_rows(&tasks, tasklist_len, None, false, None)?
+                );
+            } else if !force {
+                if !io::stdin().is_terminal() {
+                    return Err(format!(
+                        "can't ask for confirmation without a terminal, pass '{}' to remove the tasks",
+                        "--force".bold(),
+                    ));
+                }
+
+                if !confirm(&format!(
+                    "{} the following tasks will be removed\n{}Do you wish to continue?",
+                    "warning:".yellow().bold(),
+                    format_list_rows(&tasks, tasklist_len, None, false, None)?,
+                ))? {
+                    return Ok(());
+                }
+            }
+
+            // Remove from the back so earlier removals don't shift later ones.
+            for &index in indices.iter().rev() {
+                tasklist.remove(index);
+            }
+
+            match indices.len() {
+                1 => print_success("Removed", "the specified task"),
+                n => print_success("Removed", &format!("{} tasks", n)),
+            }
+        }
+
+        Command::Top { n } => {
+            let tasks: Vec<_> = tasklist
+                .iter()
+                .rev()
+                .enumerate()
+                .filter(|(_, task)| !task.completed)
+                .take(n.get())
+                .collect();
+
+            if tasks.is_empty() {
+                NOTHING_FOUND.store(args.exit_code, atomic::Ordering::Relaxed);
+            }
+
+            if args.format == Format::Json {
+                let tasks: Vec<_> = tasks
+                    .into_iter()
+                    .map(|(index, task)| format_task_json(index + 1, task))
+                    .collect();
+
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&tasks).map_err(|err| err.to_string())?
+                );
+
+                return Ok(());
+            }
+
+            if tasks.is_empty() {
+                eprintln!("There are no pending tasks");
+                return Ok(());
+            }
+
+            print!(
+                "{}",
+                format_list_rows(&tasks, tasklist.len(), None, false, None)?
+            );
+
+            return Ok(());
+        }
+
+        Command::Tui => {
+            // Without a terminal to draw on, just print the task list.
+            if !io::stdout().is_terminal() {
+                let tasks: Vec<_> = tasklist.iter().rev().enumerate().collect();
+                let buffer = format_list_rows(&tasks, tasklist.len(), None, false, None)?;
+
+                io::stdout()
+                    .write_all(buffer.as_bytes())
+                    .map_err(|err| err.to_string())?;
+
+                return Ok(());
+            }
+
+            tui::run(&mut tasklist)?;
+        }
+
+        _ => unreachable!(),
+    }
+
+    if read_only {
+        return Ok(());
+    }
+
+    save_tasklist(tasklist_path, tasklist)
+}
+
+fn ask_more_important(task: &Task, other: &Task) -> Result<bool, String> {
+    loop {
+        eprint!(
+            "Which is more important?\n {} {}\n {} {}\n{} ",
+            "1)".yellow(),
+            escape_newlines(&task.message).green(),
+            "2)".yellow(),
+            escape_newlines(&other.message).green(),
+            "[1/2]:".cyan().bold(),
+        );
+
+        io::stderr().flush().map_err(|err| err.to_string())?;
+
+        let mut buffer = String::with_capacity(2);
+
+        if io::stdin()
+            .read_line(&mut buffer)
+            .map_err(|err| err.to_string())?
+            == 0
+        {
+            return Err("no answer was given, the task list was left unchanged".to_owned());
+        }
+
+        match buffer.trim() {
+            "1" => return Ok(true),
+            "2" => return Ok(false),
+            _ => (),
+        }
+    }
+}
+
+fn check_tasklist(tasklist_path: &path::Path) -> Result<(), String> {
+    let records =
+        parsing::parse_records(&fs::read_to_string(tasklist_path).map_err(|err| err.to_string())?)?;
+    let mut problems = 0;
+
+    for (index, record) in records.iter().enumerate() {
+        if let Err(err) = record {
+            println!(
+                " {} {}",
+                format!("record {}:", index + 1).bold(),
+                err.yellow()
+            );
+            problems += 1;
+        }
+    }
+
+    if problems > 0 {
+        return Err(format!(
+            "found {} corrupted records, run '{}' to drop them",
+            problems,
+            "gc".bold(),
+        ));
+    }
+
+    print_success(
+        "Checked",
+        &format!("{} records, no problems found", records.len()),
+    );
+
+    Ok(())
+}
+
+fn collect_garbage(tasklist_path: path::PathBuf) -> Result<(), String> {
+    let now = jiff::Zoned::now();
+    let mut tasklist = Vec::new();
+    let mut blank_lines = 0;
+    let mut invalid_records = 0;
+    let mut future_dates = 0;
+
+    let contents = fs::read_to_string(&tasklist_path).map_err(|err| err.to_string())?;
+    let records = if parsing::is_legacy_tasklist(&contents) {
+        contents
+            .lines()
+            .filter(|line| {
+                let blank = line.trim().is_empty();
+                blank_lines += blank as usize;
+                !blank
+            })
+            .map(parsing::parse_task)
+            .collect()
+    } else {
+        parsing::parse_records(&contents)?
+    };
+
+    for record in records {
+        let Ok(mut task) = record else {
+            invalid_records += 1;
+            continue;
+        };
+
+        if task.created_on > now {
+            task.created_on = now.clone();
+            future_dates += 1;
+        }
+
+        tasklist.push(task);
+    }
+
+    sort_tasklist(&mut tasklist);
+
+    let current_task_path = get_current_task_path(&tasklist_path);
+    let stale_current_task = current_task_path.exists()
+        && get_current_task_index(&tasklist_path, &tasklist).is_err();
+
+    if stale_current_task {
+        fs::remove_file(current_task_path).map_err(|err| err.to_string())?;
+    }
+
+    println!(
+        "{} the task list\n {:<width$} {}\n {:<width$} {}\n {:<width$} {}\n {:<width$} {}\n {:<width$} {}",
+        "Cleaned".green().bold(),
+        "Blank lines removed:".bold(),
+        blank_lines.to_string().yellow(),
+        "Invalid records removed:".bold(),
+        invalid_records.to_string().yellow(),
+        "Future dates repaired:".bold(),
+        future_dates.to_string().yellow(),
+        "Stale current task:".bold(),
+        if stale_current_task { "removed" } else { "none" }.yellow(),
+        "Tasks kept:".bold(),
+        tasklist.len().to_string().yellow(),
+        width = 24,
+    );
+
+    save_tasklist(tasklist_path, tasklist)
+}
+
+fn colorize_message(task: &Task, message: &str) -> colored::ColoredString {
+    let now = jiff::Zoned::now().with_time_zone(tz::TimeZone::system());
+
+    if task.completed {
+        message.dimmed().strikethrough()
+    } else if is_overdue(task, &now) {
+        message.red()
+    } else if task
+        .due
+        .as_ref()
+        .is_some_and(|due| due.with_time_zone(tz::TimeZone::system()).date() == now.date())
+    {
+        message.yellow()
+    } else {
+        message.green()
+    }
+}
+
+fn colorize_priority(priority: &Priority, plain: bool) -> colored::ColoredString {
+    let string = priority.to_string();
+
+    match priority {
+        _ if plain => string.cyan(),
+        Priority::Max => string.red().bold(),
+        Priority::Min => string.bright_black().dimmed(),
+        Priority::Value(_) => string.cyan(),
+    }
+}
+
+fn colors_disabled(
+    no_color: bool,
+    no_color_var: Option<ffi::OsString>,
+    clicolor_force_var: Option<ffi::OsString>,
+    is_terminal: bool,
+) -> bool {
+    if no_color || no_color_var.is_some_and(|value| !value.is_empty()) {
+        return true;
+    }
+
+    if clicolor_force_var.is_some_and(|value| value != "0") {
+        return false;
+    }
+
+    !is_terminal
+}
+
+fn confirm(question: &str) -> Result<bool, String> {
+    eprint!("{} {} ", question, "[y/N]:".cyan().bold());
+
+    io::stderr().flush().map_err(|err| err.to_string())?;
+
+    let mut buffer = String::with_capacity(1);
+
+    io::stdin()
+        .read_line(&mut buffer)
+        .map_err(|err| err.to_string())?;
+
+    Ok(matches!(buffer.chars().next(), Some('y')))
+}
+
+fn edit_message(message: &str) -> Result<String, String> {
+    // The temporary directory is shared, so the name is unpredictable and an
+    // existing file (or a link planted in its place) is never written through.
+    let message_path =
+        env::temp_dir().join(format!("ryattl-{}.txt", uuid::Uuid::new_v4().simple()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(&message_path)
+        .and_then(|mut file| file.write_all(message.as_bytes()))
+        .map_err(|err| err.to_string())?;
+
+    let result = run_editor(&message_path).and_then(|status| {
+        if status.success() {
+            fs::read_to_string(&message_path).map_err(|err| err.to_string())
+        } else {
+            Err("the editor exited unsuccessfully, the task was left unchanged".to_owned())
+        }
+    });
+
+    let _ = fs::remove_file(&message_path);
+
+    result
+}
+
+fn edit_tasklist(tasklist_path: &path::Path) -> Result<(), String> {
+    let original = fs::read_to_string(tasklist_path).map_err(|err| err.to_string())?;
+
+    loop {
+        let status = run_editor(tasklist_path)?;
+
+        let result = if status.success() {
+            parsing::parse_tasklist(
+                &fs::read_to_string(tasklist_path).map_err(|err| err.to_string())?,
+            )
+            .map(drop)
+        } else {
+            Err("the editor exited unsuccessfully".to_owned())
+        };
+
+        let Err(err) = result else {
+            write_atomically(
+                &get_sibling_path(tasklist_path, BACKUP_EXTENSION),
+                original.as_bytes(),
+            )?;
+            print_success("Edited", "the task list");
+            return Ok(());
+        };
+
+        if !confirm(&format!(
+            "{} {}\nDo you wish to reopen it?",
+            "warning:".yellow().bold(),
+            err,
+        ))? {
+            write_atomically(tasklist_path, original.as_bytes())?;
+            return Err(format!("{}, the changes were discarded", err));
+        }
+    }
+}
+
+// For outputs that show each task on a single line.
+fn escape_newlines(message: &str) -> String {
+    message.replace(RECORD_SEPARATOR, "\\n")
+}
+
+fn format_created_on(created_on: &jiff::Zoned, date_format: DateFormat) -> Result<String, String> {
+    let length = match date_format {
+        DateFormat::Short => icu_datetime::options::length::Bag::from_date_time_style(
+            icu_datetime::options::length::Date::Short,
+            icu_datetime::options::length::Time::Short,
+        ),
+        DateFormat::Medium => Default::default(),
+        DateFormat::Long => icu_datetime::options::length::Bag::from_date_time_style(
+            icu_datetime::options::length::Date::Long,
+            icu_datetime::options::length::Time::Medium,
+        ),
+        DateFormat::Iso => {
+            return Ok(created_on
+                .with_time_zone(tz::TimeZone::system())
+                .strftime("%Y-%m-%d %H:%M:%S")
+                .to_string())
+        }
+    };
+
+    let created_on = {
+        let created_on = created_on.with_time_zone(tz::TimeZone::system()).datetime();
+
+        // Create ICU datetime.
+        let datetime = icu_calendar::DateTime::try_new_iso_datetime(
+            i32::from(created_on.year()),
+            // These unwraps are all guaranteed to be
+            // correct because Jiff's bounds on allowable
+            // values fit within icu's bounds.
+            u8::try_from(created_on.month()).unwrap(),
+            u8::try_from(created_on.day()).unwrap(),
+            u8::try_from(created_on.hour()).unwrap(),
+            u8::try_from(created_on.minute()).unwrap(),
+            u8::try_from(created_on.second()).unwrap(),
+        )
+        .unwrap();
+
+        icu_calendar::DateTime::new_from_iso(datetime, icu_calendar::Gregorian)
+    };
+
+    let locale = sys_locale::get_locale()
+        .and_then(|locale_string| locale_string.parse::<icu_locid::Locale>().ok())
+        .unwrap_or(locale!("en"));
+    let formatter =
+        icu_datetime::TypedDateTimeFormatter::try_new(&locale.clone().into(), length.into())
+            .map_err(|err| err.to_string())?;
 
-    tasklist.map(|mut tasklist| {
-        tasklist.sort_by_key(|task| task.priority);
-        tasklist
+    Ok(formatter.format(&created_on).to_string())
+}
+
+fn format_task_json(task_id: usize, task: &Task) -> serde_json::Value {
+    serde_json::json!({
+        "id": task_id,
+        "uuid": task.uuid,
+        "priority": task.priority,
+        "message": task.message,
+        "created_on": format_rfc3339(&task.created_on),
+        "completed": task.completed,
+        "completed_on": task.completed_on.as_ref().map(format_rfc3339),
+        "due": task.due.as_ref().map(format_rfc3339),
+        "tags": task.tags,
+        "modified_on": task.modified_on.as_ref().map(format_rfc3339),
+    })
+}
+
+fn format_list_rows(
+    tasks: &[(usize, &Task)],
+    tasklist_len: usize,
+    date_format: Option<DateFormat>,
+    uuid: bool,
+    width: Option<num::NonZeroUsize>,
+) -> Result<String, String> {
+    let id_width = tasklist_len.ilog10() as usize + 1;
+    let priority_width = tasks
+        .iter()
+        .map(|(_, task)| task.priority.to_string().len())
+        .max()
+        .unwrap_or(0);
+    let created_ons = match date_format {
+        Some(date_format) => tasks
+            .iter()
+            .map(|(_, task)| format_created_on(&task.created_on, date_format))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+
+    // Everything before the message column, and after it when showing dates.
+    let uuid_width = if uuid {
+        uuid::fmt::Hyphenated::LENGTH + 3
+    } else {
+        0
+    };
+    let prefix_width = id_width + uuid_width + priority_width + 7;
+    let suffix_width = created_ons
+        .iter()
+        .map(|created_on| created_on.chars().count() + 3)
+        .max()
+        .unwrap_or(0);
+
+    // Without a known width (e.g. when piped) the messages aren't wrapped.
+    let wrap_width = width
+        .map(num::NonZeroUsize::get)
+        .or_else(|| {
+            terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width.into())
+        })
+        .map(|width: usize| width.saturating_sub(prefix_width + suffix_width).max(1));
+
+    let message_width = tasks
+        .iter()
+        .flat_map(|(_, task)| task.message.split(RECORD_SEPARATOR))
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(wrap_width.unwrap_or(usize::MAX));
+
+    let mut buffer = String::new();
+
+    for (position, &(index, task)) in tasks.iter().enumerate() {
+        let badge = task
+            .priority
+            .to_string()
+            .color(priority_color(&task.priority));
+
+        // Text appended with '--newline' starts a line of its own.
+        let lines: Vec<_> = task
+            .message
+            .split(RECORD_SEPARATOR)
+            .flat_map(|line| match wrap_width {
+                Some(wrap_width) if line.chars().count() > wrap_width => {
+                    wrap_message(line, wrap_width)
+                }
+                _ => vec![line.to_owned()],
+            })
+            .collect();
+
+        buffer.push_str(&format!(
+            " {:^id_width$} | {:>priority_width$} | ",
+            (index + 1).to_string().yellow(),
+            badge,
+        ));
+
+        if uuid {
+            buffer.push_str(&format!("{} | ", task.uuid.to_string().dimmed()));
+        }
+
+        match created_ons.get(position) {
+            Some(created_on) => buffer.push_str(&format!(
+                "{:<message_width$} | {}\n",
+                colorize_message(task, &lines[0]),
+                created_on.blue(),
+            )),
+            None => buffer.push_str(&format!("{}\n", colorize_message(task, &lines[0]))),
+        }
+
+        for line in &lines[1..] {
+            buffer.push_str(&format!(
+                "{:prefix_width$}{}\n",
+                "",
+                colorize_message(task, line),
+            ));
+        }
+    }
+
+    Ok(buffer)
+}
+
+fn format_rfc3339(zoned: &jiff::Zoned) -> String {
+    zoned.strftime("%Y-%m-%dT%H:%M:%S%.f%:z").to_string()
+}
+
+fn get_committed_tasks(
+    tasklist_path: &path::Path,
+    reference: &str,
+) -> Result<Option<collections::HashSet<uuid::Uuid>>, String> {
+    let tasklist_dir = tasklist_path.parent().unwrap_or(path::Path::new("."));
+    let git = |args: &[&ffi::OsStr]| {
+        process::Command::new("git")
+            .arg("-C")
+            .arg(tasklist_dir)
+            .args(args)
+            .stderr(process::Stdio::null())
+            .output()
+    };
+
+    match git(&["rev-parse".as_ref(), "--is-inside-work-tree".as_ref()]) {
+        Ok(output) if output.status.success() => (),
+        _ => {
+            print_warning(&format!(
+                "the task list isn't inside a git repository, ignoring '{}'",
+                "--since-commit".bold(),
+            ));
+            return Ok(None);
+        }
+    }
+
+    let commit = format!("{}^{{commit}}", reference);
+    let output = git(&["rev-parse".as_ref(), "--verify".as_ref(), commit.as_ref()])
+        .map_err(|err| err.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("unknown git revision '{}'", reference.yellow()));
+    }
+
+    let mut object = ffi::OsString::from(format!("{}:./", reference));
+    object.push(tasklist_path.file_name().unwrap_or_default());
+
+    let output = git(&["show".as_ref(), &object]).map_err(|err| err.to_string())?;
+
+    // The task list didn't exist yet at that commit.
+    if !output.status.success() {
+        return Ok(Some(collections::HashSet::new()));
+    }
+
+    let contents = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(
+        parsing::parse_records(&contents)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(|task| get_uuid(&task))
+            .collect(),
+    ))
+}
+
+fn get_config() -> Result<Config, String> {
+    let Some(config_path) = dirs::config_dir().map(|dir| dir.join("ryattl").join("config.toml"))
+    else {
+        return Ok(Config::default());
+    };
+
+    let contents = match fs::read_to_string(&config_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => return Err(err.to_string()),
+    };
+
+    toml::from_str(&contents)
+        .map_err(|err| format!("couldn't parse '{}': {}", config_path.display(), err))
+}
+
+fn get_current_task_index(tasklist_path: &path::Path, tasklist: &[Task]) -> Result<usize, String> {
+    let uuid = match fs::read_to_string(get_current_task_path(tasklist_path)) {
+        Ok(string) => string,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err("there is no current task, view one with 'info' first".to_owned())
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+
+    uuid.trim()
+        .parse::<uuid::Uuid>()
+        .ok()
+        .and_then(|uuid| tasklist.iter().position(|task| task.uuid == uuid))
+        .ok_or_else(|| "the current task no longer exists, view one with 'info' first".to_owned())
+}
+
+fn get_current_task_path(tasklist_path: &path::Path) -> path::PathBuf {
+    get_sibling_path(tasklist_path, CURRENT_TASK_EXTENSION)
+}
+
+fn get_editor() -> ffi::OsString {
+    env::var_os("EDITOR").unwrap_or_else(|| {
+        if cfg!(windows) {
+            "notepad".into()
+        } else {
+            "vi".into()
+        }
     })
 }
 
-fn get_tasklist_path() -> Result<path::PathBuf, String> {
+fn get_now() -> Result<jiff::Zoned, String> {
+    // A fixed time keeps the results of the date filters reproducible.
+    match env::var("RYATTL_NOW") {
+        Ok(value) if !value.is_empty() => value
+            .parse()
+            .map_err(|err| format!("invalid value for RYATTL_NOW: {}", err)),
+        _ => Ok(jiff::Zoned::now()),
+    }
+}
+
+fn get_period_start(period: Period) -> Result<jiff::Zoned, String> {
+    let now = get_now()?.with_time_zone(tz::TimeZone::system());
+
+    match period {
+        Period::Today => now.start_of_day(),
+        Period::Week => now.start_of_day().and_then(|today| {
+            today.checked_sub(jiff::Span::new().days(now.weekday().to_monday_zero_offset()))
+        }),
+        Period::Month => now.first_of_month().and_then(|month| month.start_of_day()),
+        Period::Year => now.first_of_year().and_then(|year| year.start_of_day()),
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn get_sibling_path(tasklist_path: &path::Path, extension: &str) -> path::PathBuf {
+    let mut filename = tasklist_path.file_name().unwrap_or_default().to_owned();
+    filename.push(".");
+    filename.push(extension);
+
+    tasklist_path.with_file_name(filename)
+}
+
+fn get_similarity(message: &str, other: &str) -> f64 {
+    let normalize = |message: &str| -> Vec<char> {
+        message
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+            .chars()
+            .collect()
+    };
+
+    let message = normalize(message);
+    let other = normalize(other);
+
+    if message.is_empty() && other.is_empty() {
+        return 1.0;
+    }
+
+    // Levenshtein distance, keeping only the previous row of the matrix.
+    let mut row: Vec<usize> = (0..=other.len()).collect();
+
+    for (i, a) in message.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, b) in other.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    1.0 - row[other.len()] as f64 / message.len().max(other.len()) as f64
+}
+
+fn get_tasklist(tasklist_path: &path::Path) -> Result<Vec<Task>, String> {
+    let tasklist =
+        parsing::parse_tasklist(&fs::read_to_string(tasklist_path).map_err(|err| err.to_string())?);
+
+    let mut tasklist = tasklist?;
+    sort_tasklist(&mut tasklist);
+
+    for task in tasklist.iter_mut() {
+        task.uuid = get_uuid(task);
+    }
+
+    Ok(tasklist)
+}
+
+fn get_uuid(task: &Task) -> uuid::Uuid {
+    if !task.uuid.is_nil() {
+        return task.uuid;
+    }
+
+    // Tasks stored before UUIDs existed get one derived from their creation
+    // date, so it's the same every time the task list is read until it's kept
+    // by the next save.
+    uuid::Builder::from_custom_bytes(task.created_on.timestamp().as_nanosecond().to_be_bytes())
+        .into_uuid()
+}
+
+fn get_home_dir() -> Result<path::PathBuf, String> {
+    env::home_dir().ok_or_else(|| "couldn't determine the home directory".to_owned())
+}
+
+fn get_tasklist_filename(list: Option<&str>) -> String {
+    match list {
+        Some(name) => format!("{}.{}", TASKLIST_FILENAME, name),
+        None => TASKLIST_FILENAME.to_owned(),
+    }
+}
+
+fn get_tasklist_path(global: bool, tasklist_filename: &str) -> Result<path::PathBuf, String> {
+    if global {
+        let tasklist_path = get_home_dir()?.join(tasklist_filename);
+
+        if !tasklist_path.is_file() {
+            return Err("there is no global task list, create one with 'init --global'".to_owned());
+        }
+
+        return Ok(tasklist_path);
+    }
+
+    let home_dir = env::home_dir();
     let mut tasklist_dir = env::current_dir().map_err(|err| err.to_string())?;
 
     loop {
-        let tasklist_path = tasklist_dir.join(TASKLIST_FILENAME);
+        let tasklist_path = tasklist_dir.join(tasklist_filename);
 
         if tasklist_path.exists() && tasklist_path.is_file() {
             break;
         }
 
+        // Don't pick up an unrelated task list from above the home directory
+        // or the root of the repository.
+        if home_dir.as_ref() == Some(&tasklist_dir) || tasklist_dir.join(".git").exists() {
+            return Err("this directory has no task list associated with it".to_owned());
+        }
+
         match tasklist_dir.parent() {
             Some(pathbuf) => tasklist_dir = pathbuf.to_owned(),
             None => return Err("this directory has no task list associated with it".to_owned()),
         }
     }
 
-    Ok(tasklist_dir.join(TASKLIST_FILENAME))
+    Ok(tasklist_dir.join(tasklist_filename))
 }
 
 fn build_invalid_task_id_error(task_id: usize, tasklist_len: usize) -> String {
+    if tasklist_len == 0 {
+        return format!(
+            "invalid value '{}' for '{}': the task list is empty",
+            task_id.to_string().yellow(),
+            "<TASK_ID>".bold(),
+        );
+    }
+
     format!(
         "invalid value '{}' for '{}': expected a value less than or equal to {}\n\nFor more information, try '{}'.",
         task_id.to_string().yellow(),
@@ -371,23 +2574,557 @@ fn build_invalid_task_id_error(task_id: usize, tasklist_len: usize) -> String {
     )
 }
 
-fn save_tasklist(tasklist_path: path::PathBuf, tasklist: Vec<Task>) -> Result<(), String> {
+fn priority_color(priority: &Priority) -> colored::Color {
+    match *priority {
+        Priority::Max => colored::Color::BrightRed,
+        Priority::Value(value) if value >= parsing::HIGH_PRIORITY => colored::Color::Red,
+        Priority::Value(value) if value >= parsing::MEDIUM_PRIORITY => colored::Color::Yellow,
+        Priority::Value(_) | Priority::Min => colored::Color::Green,
+    }
+}
+
+fn parse_imported_task(entry: serde_json::Value, priority: Priority) -> Result<Task, String> {
+    let entry: ImportedTask = serde_json::from_value(entry).map_err(|err| err.to_string())?;
+
+    if entry.message.trim().is_empty() {
+        return Err("the message is empty".to_owned());
+    }
+
+    let mut tags = entry
+        .tags
+        .iter()
+        .map(|tag| parsing::parse_tag(tag))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    tags.sort();
+    tags.dedup();
+
+    Ok(Task {
+        due: entry.due.as_deref().map(parsing::parse_due).transpose()?,
+        tags,
+        ..Task::new(
+            sanitize_message(&entry.message),
+            entry.priority.unwrap_or(priority),
+        )
+    })
+}
+
+fn print_success(verb: &str, message: &str) {
+    if QUIET.load(atomic::Ordering::Relaxed) {
+        return;
+    }
+
+    if DRY_RUN.load(atomic::Ordering::Relaxed) {
+        println!(
+            "{} {} {}",
+            verb.green().bold(),
+            message,
+            "(dry run)".dimmed()
+        );
+    } else {
+        println!("{} {}", verb.green().bold(), message);
+    }
+}
+
+fn print_warning(message: &str) {
+    if !QUIET.load(atomic::Ordering::Relaxed) {
+        eprintln!("{} {}", "warning:".yellow().bold(), message);
+    }
+}
+
+fn resolve_task_ref(task_ref: &TaskRef, tasklist: &[Task]) -> Result<usize, String> {
+    let prefix = match task_ref {
+        TaskRef::Id(task_id) => return Ok(*task_id),
+        TaskRef::Uuid(prefix) => prefix,
+    };
+
+    let mut matches = tasklist
+        .iter()
+        .enumerate()
+        .filter(|(_, task)| task.uuid.to_string().starts_with(prefix.as_str()));
+
+    match (matches.next(), matches.next()) {
+        (Some((index, _)), None) => Ok(tasklist.len() - index),
+        (None, _) => Err(format!(
+            "no task has a UUID starting with '{}'",
+            prefix.yellow()
+        )),
+        (Some(_), Some(_)) => Err(format!(
+            "more than one task has a UUID starting with '{}'",
+            prefix.yellow()
+        )),
+    }
+}
+
+fn run_editor(path: &path::Path) -> Result<process::ExitStatus, String> {
+    let editor = get_editor();
+    let launch_error =
+        |err: &dyn fmt::Display| format!("couldn't launch '{}': {}", editor.to_string_lossy(), err);
+
+    // Like with git, $EDITOR is a command line, so it can have arguments
+    // (e.g. 'code --wait').
+    let mut command = if cfg!(windows) {
+        let command_line = editor.to_string_lossy();
+        let mut words = command_line.split_whitespace();
+        let mut command = process::Command::new(words.next().unwrap_or("notepad"));
+        command.args(words);
+        command
+    } else {
+        let mut script = editor.clone();
+        script.push(r#" "$@""#);
+
+        let mut command = process::Command::new("sh");
+        command.arg("-c").arg(script).arg(&editor);
+        command
+    };
+
+    let status = command
+        .arg(path)
+        .status()
+        .map_err(|err| launch_error(&err))?;
+
+    // The shell exits with 127 when it can't find the editor.
+    if cfg!(unix) && status.code() == Some(127) {
+        return Err(launch_error(&"command not found"));
+    }
+
+    Ok(status)
+}
+
+fn print_rows(rows: Vec<(&str, String)>) {
     let mut buffer = String::new();
 
-    for task in tasklist.into_iter() {
-        buffer.push_str(&format!(
-            "{}{US}{}{US}{}{RS}",
-            task.priority,
-            task.message,
-            task.created_on,
-            US = UNIT_SEPARATOR,
-            RS = RECORD_SEPARATOR,
-        ));
+    for (label, value) in rows {
+        // Multi-line values stay aligned with the first line.
+        let value = value.replace(RECORD_SEPARATOR, &format!("{}{:12}", RECORD_SEPARATOR, ""));
+        buffer.push_str(&format!(" {:<width$} {}\n", label.bold(), value, width = 10));
+    }
+
+    print!("{}", buffer);
+}
+
+fn sanitize_message(message: &str) -> String {
+    // Newlines are kept, the JSON format stores them escaped.
+    message
+        .chars()
+        .map(|c| match c {
+            UNIT_SEPARATOR => ' ',
+            c => c,
+        })
+        .collect()
+}
+
+fn humanize_span(span: jiff::Span) -> String {
+    // Spans measured in years and months carry the weeks in their days.
+    let days = i64::from(span.get_days());
+    let units = [
+        (span.get_years().into(), "year"),
+        (span.get_months().into(), "month"),
+        (i64::from(span.get_weeks()) + days / 7, "week"),
+        (days % 7, "day"),
+        (span.get_hours().into(), "hour"),
+        (span.get_minutes(), "minute"),
+    ];
+
+    let Some((amount, unit)) = units.into_iter().find(|(amount, _): &(i64, _)| *amount != 0)
+    else {
+        return "just now".to_owned();
+    };
+
+    let plural = if amount.abs() == 1 { "" } else { "s" };
+
+    if amount < 0 {
+        format!("in {} {}{}", -amount, unit, plural)
+    } else {
+        format!("{} {}{} ago", amount, unit, plural)
+    }
+}
+
+fn insert_task(tasklist: &mut Vec<Task>, mut task: Task) {
+    let mut begin = 0;
+    let mut end = tasklist.len();
+
+    // The newest sequence number puts the task before the existing ones of
+    // its priority, so it's shown after them.
+    task.sequence = tasklist.iter().map(|task| task.sequence).max().unwrap_or(0) + 1;
+
+    while begin < end {
+        let pivot = (begin + end) / 2;
+        match tasklist[pivot].sort_key().cmp(&task.sort_key()) {
+            cmp::Ordering::Less => {
+                begin = pivot + 1;
+            }
+            cmp::Ordering::Equal | cmp::Ordering::Greater => end = pivot,
+        }
+    }
+
+    tasklist.insert(begin, task);
+}
+
+fn is_overdue(task: &Task, now: &jiff::Zoned) -> bool {
+    !task.completed && task.due.as_ref().is_some_and(|due| due < now)
+}
+
+fn lock_tasklist(tasklist_path: &path::Path) -> Result<fs::File, String> {
+    // A separate lock file is used because saving replaces the task list file,
+    // which would leave a lock on the old one protecting nothing.
+    let lock_file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(get_sibling_path(tasklist_path, LOCK_EXTENSION))
+        .map_err(|err| err.to_string())?;
+    let started_on = time::Instant::now();
+
+    loop {
+        match lock_file.try_lock() {
+            Ok(()) => return Ok(lock_file),
+            Err(fs::TryLockError::WouldBlock) if started_on.elapsed() < LOCK_TIMEOUT => {
+                thread::sleep(time::Duration::from_millis(50));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                return Err("the task list is locked by another process".to_owned())
+            }
+            Err(fs::TryLockError::Error(err)) => return Err(err.to_string()),
+        }
+    }
+}
+
+fn save_tasklist(tasklist_path: path::PathBuf, tasklist: Vec<Task>) -> Result<(), String> {
+    if DRY_RUN.load(atomic::Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let mut buffer = serde_json::to_string_pretty(&StoredTasklist {
+        version: TASKLIST_VERSION,
+        tasks: tasklist,
+    })
+    .map_err(|err| err.to_string())?;
+    buffer.push('\n');
+
+    // Commands that only read the task list still end up here, and they
+    // shouldn't replace the backup of the last actual change.
+    if fs::read(&tasklist_path).is_ok_and(|contents| contents == buffer.as_bytes()) {
+        return Ok(());
+    }
+
+    // Keep the previous version of the task list around for 'undo'.
+    fs::copy(
+        &tasklist_path,
+        get_sibling_path(&tasklist_path, BACKUP_EXTENSION),
+    )
+    .map_err(|err| err.to_string())?;
+
+    write_atomically(&tasklist_path, buffer.as_bytes())
+}
+
+fn set_current_task(tasklist_path: &path::Path, uuid: &uuid::Uuid) -> Result<(), String> {
+    if DRY_RUN.load(atomic::Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    fs::write(get_current_task_path(tasklist_path), uuid.to_string()).map_err(|err| err.to_string())
+}
+
+// Numbers the tasks in their current order, for when a task was placed by
+// position instead of with insert_task.
+fn renumber_tasks(tasklist: &mut [Task]) {
+    let tasklist_len = tasklist.len();
+
+    for (index, task) in tasklist.iter_mut().enumerate() {
+        task.sequence = (tasklist_len - index) as u64;
+    }
+}
+
+fn sort_tasklist(tasklist: &mut [Task]) {
+    // Task lists stored before sequence numbers existed keep their order.
+    if tasklist.iter().any(|task| task.sequence == 0) {
+        tasklist.sort_by_key(|task| task.priority);
+        renumber_tasks(tasklist);
+    }
+
+    tasklist.sort_by_key(Task::sort_key);
+}
+
+fn swap_tasks(tasklist: &mut [Task], index: usize, other: usize) {
+    // Exchanging the priorities and sequence numbers along with the positions
+    // keeps the list sorted, even when both tasks share a priority.
+    let priority = tasklist[index].priority;
+    tasklist[index].priority = tasklist[other].priority;
+    tasklist[other].priority = priority;
+
+    let sequence = tasklist[index].sequence;
+    tasklist[index].sequence = tasklist[other].sequence;
+    tasklist[other].sequence = sequence;
+
+    tasklist.swap(index, other);
+}
+
+fn wrap_message(message: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+
+    for mut word in message.split_whitespace() {
+        let mut word_len = word.chars().count();
+
+        if line_len > 0 && line_len + 1 + word_len > width {
+            lines.push(std::mem::take(&mut line));
+            line_len = 0;
+        }
+
+        // Words too long for a line of their own are broken up.
+        while word_len > width {
+            let (split, _) = word.char_indices().nth(width).unwrap_or((word.len(), ' '));
+            lines.push(word[..split].to_owned());
+            word = &word[split..];
+            word_len -= width;
+        }
+
+        if line_len > 0 {
+            line.push(' ');
+            line_len += 1;
+        }
+
+        line.push_str(word);
+        line_len += word_len;
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
     }
 
-    let mut tasklist_file = fs::File::create(tasklist_path).map_err(|err| err.to_string())?;
+    lines
+}
+
+fn write_atomically(path: &path::Path, contents: &[u8]) -> Result<(), String> {
+    let temporary_path = get_sibling_path(path, TEMPORARY_EXTENSION);
+
+    // Write everything to a temporary file first and then move it over the
+    // original, so a crash midway never leaves a truncated file behind.
+    let result = fs::File::create(&temporary_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temporary_path, path));
+
+    result.map_err(|err| {
+        let _ = fs::remove_file(&temporary_path);
+        err.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(priority: usize, message: &str) -> Task {
+        Task::new(message.to_owned(), Priority::Value(priority))
+    }
+
+    // The list is displayed in reverse, so these are in the order shown.
+    fn messages(tasklist: &[Task]) -> Vec<&str> {
+        tasklist
+            .iter()
+            .rev()
+            .map(|task| task.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn colors_are_disabled_when_piped() {
+        assert!(!colors_disabled(false, None, None, true));
+        assert!(colors_disabled(false, None, None, false));
+    }
+
+    #[test]
+    fn colors_are_disabled_by_no_color() {
+        assert!(colors_disabled(true, None, None, true));
+        assert!(colors_disabled(false, Some("1".into()), None, true));
+        assert!(!colors_disabled(false, Some("".into()), None, true));
+        assert!(colors_disabled(true, None, Some("1".into()), true));
+    }
+
+    #[test]
+    fn colors_are_forced_by_clicolor_force() {
+        assert!(!colors_disabled(false, None, Some("1".into()), false));
+        assert!(colors_disabled(false, None, Some("0".into()), false));
+    }
+
+    #[test]
+    fn insert_task_keeps_tasks_of_equal_priority_in_insertion_order() {
+        let mut tasklist = Vec::new();
+
+        for message in ["a", "b", "c"] {
+            insert_task(&mut tasklist, task(5, message));
+        }
+
+        assert_eq!(messages(&tasklist), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn insert_task_keeps_the_list_sorted() {
+        let mut tasklist = Vec::new();
+
+        for (priority, message) in [(5, "a"), (1, "b"), (9, "c"), (5, "d"), (1, "e"), (9, "f")] {
+            insert_task(&mut tasklist, task(priority, message));
+        }
+
+        let mut sorted: Vec<_> = tasklist.iter().rev().cloned().collect();
+        sort_tasklist(&mut sorted);
+
+        assert_eq!(messages(&tasklist), ["c", "f", "a", "d", "b", "e"]);
+        assert_eq!(messages(&sorted), messages(&tasklist));
+    }
+
+    #[test]
+    fn sort_tasklist_keeps_the_order_of_tasks_without_sequence_numbers() {
+        let mut tasklist = vec![task(1, "d"), task(5, "c"), task(5, "b"), task(5, "a")];
+
+        sort_tasklist(&mut tasklist);
+
+        assert_eq!(messages(&tasklist), ["a", "b", "c", "d"]);
+        assert!(tasklist.iter().all(|task| task.sequence > 0));
+    }
+
+    #[test]
+    fn humanize_span_uses_the_largest_unit() {
+        let cases = [
+            (jiff::Span::new(), "just now"),
+            (jiff::Span::new().seconds(59), "just now"),
+            (jiff::Span::new().minutes(1), "1 minute ago"),
+            (jiff::Span::new().minutes(59), "59 minutes ago"),
+            (jiff::Span::new().hours(1).minutes(30), "1 hour ago"),
+            (jiff::Span::new().days(6).hours(23), "6 days ago"),
+            (jiff::Span::new().days(7), "1 week ago"),
+            (jiff::Span::new().days(13), "1 week ago"),
+            (jiff::Span::new().days(14), "2 weeks ago"),
+            (jiff::Span::new().months(1).days(20), "1 month ago"),
+            (jiff::Span::new().years(2).months(3), "2 years ago"),
+            (jiff::Span::new().days(-2), "in 2 days"),
+        ];
+
+        for (span, expected) in cases {
+            assert_eq!(humanize_span(span), expected);
+        }
+    }
+
+    #[test]
+    fn priority_color_follows_the_thresholds() {
+        use colored::Color;
+        use parsing::{HIGH_PRIORITY, MEDIUM_PRIORITY};
+
+        let cases = [
+            (Priority::Max, Color::BrightRed),
+            (Priority::Value(HIGH_PRIORITY), Color::Red),
+            (Priority::Value(HIGH_PRIORITY - 1), Color::Yellow),
+            (Priority::Value(MEDIUM_PRIORITY), Color::Yellow),
+            (Priority::Value(MEDIUM_PRIORITY - 1), Color::Green),
+            (Priority::Value(0), Color::Green),
+            (Priority::Min, Color::Green),
+        ];
+
+        for (priority, color) in cases {
+            assert_eq!(priority_color(&priority), color);
+        }
+    }
+
+    #[test]
+    fn colorize_priority_styles_min_and_max_apart_unless_plain() {
+        let numeric = colorize_priority(&Priority::Value(5), false);
+        let max = colorize_priority(&Priority::Max, false);
+        let min = colorize_priority(&Priority::Min, false);
+
+        assert_eq!(numeric.fgcolor, Some(colored::Color::Cyan));
+        assert_eq!(max.fgcolor, Some(colored::Color::Red));
+        assert!(max.style.contains(colored::Styles::Bold));
+        assert_eq!(min.fgcolor, Some(colored::Color::BrightBlack));
+        assert!(min.style.contains(colored::Styles::Dimmed));
+
+        for priority in [Priority::Max, Priority::Min] {
+            let plain = colorize_priority(&priority, true);
+
+            assert_eq!(plain.fgcolor, numeric.fgcolor);
+            assert_eq!(plain.style, numeric.style);
+        }
+    }
+
+    #[test]
+    fn sort_key_compare_follows_the_direction() {
+        let low = task(1, "b");
+        let high = Task {
+            created_on: &low.created_on + jiff::Span::new().hours(1),
+            ..task(2, "a")
+        };
+        let key = |field, descending| SortKey { field, descending };
+
+        assert_eq!(
+            key(SortField::Priority, true).compare(&high, &low),
+            cmp::Ordering::Less
+        );
+        assert_eq!(
+            key(SortField::Priority, false).compare(&high, &low),
+            cmp::Ordering::Greater
+        );
+        assert_eq!(
+            key(SortField::Created, false).compare(&low, &high),
+            cmp::Ordering::Less
+        );
+        assert_eq!(
+            key(SortField::Message, false).compare(&low, &high),
+            cmp::Ordering::Greater
+        );
+        assert_eq!(
+            key(SortField::Message, true).compare(&low, &low),
+            cmp::Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn write_atomically_replaces_a_partial_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".ryattl");
+        let temporary_path = get_sibling_path(&path, TEMPORARY_EXTENSION);
+
+        fs::write(&path, "original").unwrap();
+
+        // A crash between writing and renaming leaves the original intact.
+        fs::write(&temporary_path, "parti").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+
+        write_atomically(&path, b"updated").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "updated");
+        assert!(!temporary_path.exists());
+    }
+
+    #[test]
+    fn write_atomically_keeps_the_original_when_the_write_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".ryattl");
 
-    tasklist_file
-        .write_all(buffer.as_bytes())
-        .map_err(|err| err.to_string())
+        fs::write(&path, "original").unwrap();
+        fs::create_dir(get_sibling_path(&path, TEMPORARY_EXTENSION)).unwrap();
+
+        assert!(write_atomically(&path, b"updated").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "original");
+    }
+
+    #[test]
+    fn wrap_message_breaks_at_word_boundaries() {
+        assert_eq!(
+            wrap_message("the quick brown fox jumps", 10),
+            ["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap_message("fits", 10), ["fits"]);
+        assert_eq!(wrap_message("", 10), [""]);
+    }
+
+    #[test]
+    fn wrap_message_breaks_up_words_longer_than_a_line() {
+        assert_eq!(
+            wrap_message("a abcdefghij bc", 4),
+            ["a", "abcd", "efgh", "ij", "bc"]
+        );
+    }
 }
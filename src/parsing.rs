@@ -13,17 +13,138 @@
 //    You should have received a copy of the GNU General Public License
 //    along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::num;
+use std::{num, ops};
 
-use crate::{Priority, Task};
+use crate::{
+    DateFormat, Priority, SortField, SortKey, StoredTasklist, Task, TaskRef, BACKUP_EXTENSION,
+    CURRENT_TASK_EXTENSION, LOCK_EXTENSION, TEMPORARY_EXTENSION,
+};
 
 pub const RECORD_SEPARATOR: char = '\n';
 pub const UNIT_SEPARATOR: char = '\x1F';
+pub const TAG_SEPARATOR: char = ',';
+
+pub const LOW_PRIORITY: usize = 1;
+pub const MEDIUM_PRIORITY: usize = 50;
+pub const HIGH_PRIORITY: usize = 100;
+
+pub const TASKLIST_VERSION: u32 = 1;
+
+// Task lists written before the JSON format are separator-delimited records.
+pub fn is_legacy_tasklist(string: &str) -> bool {
+    !string.trim_start().starts_with('{')
+}
+
+pub fn parse_date_format(string: &str) -> Result<DateFormat, String> {
+    match string.trim() {
+        "short" => Ok(DateFormat::Short),
+        "medium" => Ok(DateFormat::Medium),
+        "long" => Ok(DateFormat::Long),
+        "iso" => Ok(DateFormat::Iso),
+        _ => Err("expected 'short', 'medium', 'long' or 'iso'".to_owned()),
+    }
+}
+
+pub fn parse_date(string: &str) -> Result<jiff::Zoned, String> {
+    let string = string.trim();
+    let error = || {
+        "expected a date (YYYY-MM-DD) or a duration like '12h ago', '3d ago' or '1w ago'".to_owned()
+    };
+
+    if let Some(span) = string.strip_suffix("ago") {
+        return jiff::Zoned::now()
+            .checked_sub(parse_span(span.trim_end(), error)?)
+            .map_err(|_| "the duration is too long".to_owned());
+    }
+
+    if let Ok(zoned) = string.parse::<jiff::Zoned>() {
+        return Ok(zoned);
+    }
+
+    // A bare date starts at the beginning of that day. Parsing it as a date
+    // instead would silently drop the time of a full datetime.
+    string
+        .parse::<jiff::civil::DateTime>()
+        .map_err(|_| error())?
+        .to_zoned(jiff::tz::TimeZone::system())
+        .map_err(|err| err.to_string())
+}
+
+pub fn parse_due(string: &str) -> Result<jiff::Zoned, String> {
+    let string = string.trim();
+    let system = jiff::tz::TimeZone::system();
+
+    if let Ok(zoned) = string.parse::<jiff::Zoned>() {
+        return Ok(zoned);
+    }
+
+    if let Ok(datetime) = string.parse::<jiff::civil::DateTime>() {
+        // A bare date is due by the end of that day. A date also parses from
+        // a full datetime, so the time separator is what tells them apart.
+        let datetime = if string.contains(['T', 't', ' ']) {
+            datetime
+        } else {
+            datetime.date().to_datetime(jiff::civil::Time::MAX)
+        };
+
+        return datetime.to_zoned(system).map_err(|err| err.to_string());
+    }
+
+    let error = || "expected a date (YYYY-MM-DD) or a duration like '12h', '3d' or '1w'".to_owned();
+
+    jiff::Zoned::now()
+        .checked_add(parse_span(string, error)?)
+        .map_err(|_| "the duration is too long".to_owned())
+}
+
+pub fn parse_list_name(string: &str) -> Result<String, String> {
+    if string.is_empty() {
+        return Err("expected a non-empty list name".to_owned());
+    }
+
+    if !string
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+    {
+        return Err("a list name can only contain letters, digits, '-' and '_'".to_owned());
+    }
+
+    if [
+        BACKUP_EXTENSION,
+        CURRENT_TASK_EXTENSION,
+        LOCK_EXTENSION,
+        TEMPORARY_EXTENSION,
+    ]
+    .contains(&string)
+    {
+        return Err(format!(
+            "'{}' is reserved and can't be used as a list name",
+            string
+        ));
+    }
+
+    Ok(string.to_owned())
+}
+
+pub fn parse_message(string: &str) -> Result<String, String> {
+    // Separators are replaced with spaces when the message is stored.
+    if string
+        .chars()
+        .all(|c| c.is_whitespace() || matches!(c, RECORD_SEPARATOR | UNIT_SEPARATOR))
+    {
+        return Err("expected a non-empty message".to_owned());
+    }
+
+    Ok(string.to_owned())
+}
 
 pub fn parse_priority(string: &str) -> Result<Priority, String> {
     match string.trim() {
         "max" => Ok(Priority::Max),
         "min" => Ok(Priority::Min),
+        "low" => Ok(Priority::Value(LOW_PRIORITY)),
+        "medium" => Ok(Priority::Value(MEDIUM_PRIORITY)),
+        "high" => Ok(Priority::Value(HIGH_PRIORITY)),
         _ => string
             .parse()
             .map(Priority::Value)
@@ -31,33 +152,179 @@ pub fn parse_priority(string: &str) -> Result<Priority, String> {
                 num::IntErrorKind::PosOverflow => {
                     "the number is too big, you might want to use 'max' instead".to_owned()
                 }
-                _ => "expected 'min', 'max' or a whole number".to_owned(),
+                _ => "expected 'min', 'low', 'medium', 'high', 'max' or a whole number".to_owned(),
             }),
     }
 }
 
+pub fn parse_priority_offset(string: &str) -> Result<isize, String> {
+    string
+        .trim()
+        .parse()
+        .map_err(|err: num::ParseIntError| match err.kind() {
+            num::IntErrorKind::PosOverflow | num::IntErrorKind::NegOverflow => {
+                "the number is too big to be a valid offset".to_owned()
+            }
+            _ => "expected a whole number optionally prefixed with '+' or '-'".to_owned(),
+        })
+}
+
+pub fn parse_records(string: &str) -> Result<Vec<Result<Task, String>>, String> {
+    if is_legacy_tasklist(string) {
+        return Ok(string.lines().map(parse_task).collect());
+    }
+
+    let tasklist: StoredTasklist<serde_json::Value> = serde_json::from_str(string)
+        .map_err(|err| format!("the task list file is corrupted: {}", err))?;
+
+    if tasklist.version != TASKLIST_VERSION {
+        return Err(format!(
+            "the task list file has an unsupported version ({})",
+            tasklist.version
+        ));
+    }
+
+    Ok(tasklist
+        .tasks
+        .into_iter()
+        .map(|task| serde_json::from_value(task).map_err(|err| err.to_string()))
+        .collect())
+}
+
+pub fn parse_similarity(string: &str) -> Result<f64, String> {
+    match string.trim().parse() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err("expected a number between 0 and 1".to_owned()),
+    }
+}
+
+pub fn parse_sort_key(string: &str) -> Result<SortKey, String> {
+    let (field, direction) = match string.trim().split_once(':') {
+        Some((field, direction)) => (field, Some(direction)),
+        None => (string.trim(), None),
+    };
+
+    let field = match field {
+        // 'age' lists the oldest tasks first, just like 'created'.
+        "created" | "age" => SortField::Created,
+        "message" => SortField::Message,
+        "priority" => SortField::Priority,
+        _ => return Err("expected 'priority', 'created', 'age' or 'message'".to_owned()),
+    };
+
+    let descending = match direction {
+        Some("asc") => false,
+        Some("desc") => true,
+        // Highest priority first, like the unsorted list.
+        None => matches!(field, SortField::Priority),
+        Some(_) => return Err("expected the direction to be 'asc' or 'desc'".to_owned()),
+    };
+
+    Ok(SortKey { field, descending })
+}
+
+fn parse_span(string: &str, error: impl Fn() -> String) -> Result<jiff::Span, String> {
+    let (unit_index, _) = string.char_indices().last().ok_or_else(&error)?;
+    let (amount, unit) = string.split_at(unit_index);
+    let amount: i64 = amount.parse().map_err(|_| error())?;
+
+    match unit {
+        "h" => jiff::Span::new().try_hours(amount),
+        "d" => jiff::Span::new().try_days(amount),
+        "w" => jiff::Span::new().try_weeks(amount),
+        _ => return Err(error()),
+    }
+    .map_err(|_| "the duration is too long".to_owned())
+}
+
+pub fn parse_tag(string: &str) -> Result<String, String> {
+    let tag: String = string
+        .trim()
+        .chars()
+        .filter(|&c| !matches!(c, RECORD_SEPARATOR | UNIT_SEPARATOR | TAG_SEPARATOR))
+        .collect();
+
+    if tag.is_empty() {
+        return Err("expected a non-empty tag".to_owned());
+    }
+
+    Ok(tag)
+}
+
 pub fn parse_task(string: &str) -> Result<Task, String> {
-    let mut items = string.splitn(3, UNIT_SEPARATOR);
+    if string.trim().is_empty() {
+        return Err("empty record".to_owned());
+    }
+
+    let mut items = string.split(UNIT_SEPARATOR);
 
     let Some(priority) = items.next().and_then(|string| parse_priority(string).ok()) else {
-        return Err("the task list file is corrupted: couldn't parse priority".to_owned());
+        return Err("invalid priority".to_owned());
     };
 
     let Some(message) = items.next() else {
-        return Err("the task list file is corrupted: couldn't parse message".to_owned());
+        return Err("missing message".to_owned());
+    };
+
+    let created_on = match items.next() {
+        None => return Err("missing creation date".to_owned()),
+        Some(string) => match string.parse() {
+            Ok(created_on) => created_on,
+            Err(_) => return Err("invalid creation date".to_owned()),
+        },
     };
 
-    let Some(created_on) = items.next().and_then(|string| string.parse().ok()) else {
-        return Err("the task list file is corrupted: couldn't parse creation date".to_owned());
+    // Task lists written before completion tracking have no such field.
+    let Some(completed) = items.next().map_or(Some(false), |string| string.parse().ok()) else {
+        return Err("invalid completion status".to_owned());
     };
 
+    let due = match items.next() {
+        None | Some("") => None,
+        Some(string) => match string.parse() {
+            Ok(due) => Some(due),
+            Err(_) => return Err("invalid due date".to_owned()),
+        },
+    };
+
+    let tags = items
+        .next()
+        .map(|string| {
+            string
+                .split(TAG_SEPARATOR)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default();
+
     Ok(Task {
-        priority,
-        message: message.to_owned(),
         created_on,
+        completed,
+        due,
+        tags,
+        // Derived from the creation date by get_tasklist.
+        uuid: uuid::Uuid::nil(),
+        ..Task::new(message.to_owned(), priority)
     })
 }
 
+pub fn parse_tasklist(string: &str) -> Result<Vec<Task>, String> {
+    parse_records(string)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, record)| {
+            record.map_err(|err| {
+                format!(
+                    "the task list file is corrupted at record {}: {}",
+                    index + 1,
+                    err
+                )
+            })
+        })
+        .collect()
+}
+
 pub fn parse_task_id(string: &str) -> Result<usize, String> {
     string
         .trim()
@@ -68,3 +335,272 @@ pub fn parse_task_id(string: &str) -> Result<usize, String> {
             _ => "expected a non-zero whole number".to_owned(),
         })
 }
+
+pub fn parse_task_ref(string: &str) -> Result<TaskRef, String> {
+    let string = string.trim();
+
+    // Plain numbers are always positional IDs.
+    if string.chars().all(|c| c.is_ascii_digit()) {
+        return parse_task_id(string).map(TaskRef::Id);
+    }
+
+    if string.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Ok(TaskRef::Uuid(string.to_ascii_lowercase()));
+    }
+
+    Err("expected a non-zero whole number or the start of a UUID".to_owned())
+}
+
+pub fn parse_task_ref_range(string: &str) -> Result<ops::RangeInclusive<TaskRef>, String> {
+    let Some((start, end)) = string.split_once("..") else {
+        return parse_task_ref(string).map(|task_ref| task_ref.clone()..=task_ref);
+    };
+
+    let (start, end) = (parse_task_ref(start)?, parse_task_ref(end)?);
+
+    if let (TaskRef::Id(start), TaskRef::Id(end)) = (&start, &end) {
+        if start > end {
+            return Err(
+                "expected the start of the range to not be greater than its end".to_owned(),
+            );
+        }
+    }
+
+    Ok(start..=end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_due_accepts_durations() {
+        let due = parse_due("3d").unwrap();
+        let days = jiff::Zoned::now().until(&due).unwrap().get_hours() / 24;
+
+        assert!((2..=3).contains(&days));
+    }
+
+    #[test]
+    fn parse_due_keeps_the_time_of_datetimes() {
+        let system = jiff::tz::TimeZone::system();
+        let date = jiff::civil::date(2024, 5, 6);
+
+        assert_eq!(
+            parse_due("2024-05-06").unwrap(),
+            date.to_datetime(jiff::civil::Time::MAX)
+                .to_zoned(system.clone())
+                .unwrap()
+        );
+        assert_eq!(
+            parse_due("2024-05-06 07:08").unwrap(),
+            date.at(7, 8, 0, 0).to_zoned(system).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_date_accepts_dates_and_durations_ago() {
+        let system = jiff::tz::TimeZone::system();
+        let date = jiff::civil::date(2024, 5, 6);
+
+        assert_eq!(
+            parse_date(" 2024-05-06 ").unwrap(),
+            date.to_zoned(system.clone()).unwrap()
+        );
+        assert_eq!(
+            parse_date("2024-05-06T07:08").unwrap(),
+            date.at(7, 8, 0, 0).to_zoned(system).unwrap()
+        );
+        assert_eq!(
+            parse_date("2024-05-06T07:08:09+00:00[UTC]").unwrap(),
+            date.at(7, 8, 9, 0).intz("UTC").unwrap()
+        );
+
+        let ago = parse_date("7d ago").unwrap();
+        let days = ago.until(&jiff::Zoned::now()).unwrap().get_hours() / 24;
+
+        assert_eq!(days, 7);
+        assert!(parse_date("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn parse_due_and_parse_date_reject_invalid_durations() {
+        for string in ["", "3", "d", "3é", "é", "3x", "3d ago"] {
+            assert!(parse_due(string).is_err(), "{:?}", string);
+        }
+
+        for string in ["ago", "3é ago", "é ago", "3 ago"] {
+            assert!(parse_date(string).is_err(), "{:?}", string);
+        }
+    }
+
+    #[test]
+    fn parse_message_rejects_blank_messages() {
+        assert_eq!(parse_message(" a ").unwrap(), " a ");
+
+        for message in ["", "  ", "\t\n", "\x1F "] {
+            assert!(parse_message(message).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_sort_key_defaults_to_the_natural_direction() {
+        let cases = [
+            ("priority", SortField::Priority, true),
+            ("created", SortField::Created, false),
+            (" age:desc ", SortField::Created, true),
+            ("message:asc", SortField::Message, false),
+            ("priority:asc", SortField::Priority, false),
+        ];
+
+        for (string, field, descending) in cases {
+            let key = parse_sort_key(string).unwrap();
+
+            assert_eq!(key.field, field, "{:?}", string);
+            assert_eq!(key.descending, descending, "{:?}", string);
+        }
+
+        for string in ["", "size", "priority:up", "created:"] {
+            assert!(parse_sort_key(string).is_err(), "{:?}", string);
+        }
+    }
+
+    #[test]
+    fn parse_priority_accepts_names() {
+        assert_eq!(
+            parse_priority("low").unwrap(),
+            Priority::Value(LOW_PRIORITY)
+        );
+        assert_eq!(
+            parse_priority("medium").unwrap(),
+            Priority::Value(MEDIUM_PRIORITY)
+        );
+        assert_eq!(
+            parse_priority(" high ").unwrap(),
+            Priority::Value(HIGH_PRIORITY)
+        );
+        assert!(parse_priority("urgent").is_err());
+    }
+
+    #[test]
+    fn named_priorities_sort_between_min_and_max() {
+        let mut priorities: Vec<_> = ["high", "max", "7", "min", "low", "medium", "500"]
+            .into_iter()
+            .map(|string| parse_priority(string).unwrap())
+            .collect();
+
+        priorities.sort();
+
+        let expected = ["min", "low", "7", "medium", "high", "500", "max"]
+            .map(|string| parse_priority(string).unwrap());
+        assert_eq!(priorities, expected);
+        assert_eq!(priorities[0], Priority::Min);
+        assert_eq!(priorities[6], Priority::Max);
+    }
+
+    #[test]
+    fn parse_task_reads_the_creation_date() {
+        let created_on: jiff::Zoned = "2024-05-06T07:08:09+00:00[UTC]".parse().unwrap();
+        let record = format!("5{0}Water the plants{0}{1}", UNIT_SEPARATOR, created_on);
+        let task = parse_task(&record).unwrap();
+
+        assert_eq!(task.priority, Priority::Value(5));
+        assert_eq!(task.message, "Water the plants");
+        assert_eq!(task.created_on, created_on);
+    }
+
+    #[test]
+    fn parse_task_rejects_a_missing_or_invalid_creation_date() {
+        let missing = format!("5{}Water the plants", UNIT_SEPARATOR);
+        let invalid = format!("5{0}Water the plants{0}yesterday", UNIT_SEPARATOR);
+
+        assert_eq!(parse_task(&missing).unwrap_err(), "missing creation date");
+        assert_eq!(parse_task(&invalid).unwrap_err(), "invalid creation date");
+    }
+
+    #[test]
+    fn separators_split_records_and_units() {
+        assert_eq!(RECORD_SEPARATOR, '\n');
+        assert_eq!(UNIT_SEPARATOR, '\x1F');
+
+        let created_on = jiff::Zoned::now();
+        let tasklist = ["a", "b"]
+            .map(|message| format!("1{0}{1}{0}{2}", UNIT_SEPARATOR, message, created_on))
+            .join(&RECORD_SEPARATOR.to_string());
+        let messages: Vec<_> = parse_tasklist(&tasklist)
+            .unwrap()
+            .into_iter()
+            .map(|task| task.message)
+            .collect();
+
+        assert_eq!(messages, ["a", "b"]);
+    }
+
+    #[test]
+    fn parse_tasklist_round_trips_the_json_format() {
+        let created_on: jiff::Zoned = "2024-05-06T07:08:09+00:00[UTC]".parse().unwrap();
+        let tasklist = serde_json::json!({
+            "version": TASKLIST_VERSION,
+            "tasks": [{
+                "priority": "max",
+                "message": "Water the plants",
+                "created_on": created_on,
+                "tags": ["home"],
+            }],
+        })
+        .to_string();
+
+        let tasks = parse_tasklist(&tasklist).unwrap();
+        let stored = serde_json::to_string(&StoredTasklist {
+            version: TASKLIST_VERSION,
+            tasks: tasks.clone(),
+        })
+        .unwrap();
+
+        assert_eq!(tasks[0].priority, Priority::Max);
+        assert_eq!(tasks[0].created_on, created_on);
+        assert_eq!(tasks[0].tags, ["home"]);
+        assert!(!tasks[0].completed);
+        assert_eq!(
+            parse_tasklist(&stored).unwrap()[0].message,
+            "Water the plants"
+        );
+    }
+
+    #[test]
+    fn parse_tasklist_round_trips_the_completion_date() {
+        let completed_on: jiff::Zoned = "2024-05-07T10:11:12+00:00[UTC]".parse().unwrap();
+        let tasklist = serde_json::json!({
+            "version": TASKLIST_VERSION,
+            "tasks": [{
+                "priority": "min",
+                "message": "Water the plants",
+                "created_on": "2024-05-06T07:08:09+00:00[UTC]",
+                "completed": true,
+                "completed_on": completed_on,
+            }],
+        })
+        .to_string();
+
+        let tasks = parse_tasklist(&tasklist).unwrap();
+        let stored = serde_json::to_string(&StoredTasklist {
+            version: TASKLIST_VERSION,
+            tasks,
+        })
+        .unwrap();
+
+        let tasks = parse_tasklist(&stored).unwrap();
+        assert!(tasks[0].completed);
+        assert_eq!(tasks[0].completed_on, Some(completed_on));
+    }
+
+    #[test]
+    fn parse_tasklist_rejects_an_unknown_version() {
+        let tasklist = r#"{"version": 2, "tasks": []}"#;
+
+        assert_eq!(
+            parse_tasklist(tasklist).unwrap_err(),
+            "the task list file has an unsupported version (2)"
+        );
+    }
+}
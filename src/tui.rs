@@ -0,0 +1,234 @@
+//    Copyright (C) 2024 Segmentation Violator <segmentationviolator@proton.me>
+
+//    This program is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+
+//    This program is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+
+//    You should have received a copy of the GNU General Public License
+//    along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use crate::{escape_newlines, sanitize_message, Priority, Task};
+
+const HELP: &str = " ↑/↓ move  d done  x remove  e edit  q quit";
+
+struct App<'a> {
+    tasklist: &'a mut Vec<Task>,
+    state: ListState,
+    // The message being edited, if any.
+    editing: Option<String>,
+}
+
+pub fn run(tasklist: &mut Vec<Task>) -> Result<(), String> {
+    let mut terminal = ratatui::init();
+    let mut app = App {
+        state: ListState::default().with_selected((!tasklist.is_empty()).then_some(0)),
+        tasklist,
+        editing: None,
+    };
+
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    result.map_err(|err| err.to_string())
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(buffer) = &mut self.editing {
+                match key.code {
+                    KeyCode::Char(c) => buffer.push(c),
+                    KeyCode::Backspace => {
+                        buffer.pop();
+                    }
+                    KeyCode::Enter => self.finish_editing(),
+                    KeyCode::Esc => self.editing = None,
+                    _ => (),
+                }
+
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+                KeyCode::Char('d') => {
+                    if let Some(task) = self.selected_task() {
+                        task.completed = true;
+                        // Marking a completed task again keeps its original date.
+                        task.completed_on.get_or_insert_with(jiff::Zoned::now);
+                    }
+                }
+                KeyCode::Char('x') => self.remove_selected(),
+                KeyCode::Char('e') => {
+                    self.editing = self.selected_task().map(|task| task.message.clone());
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [list_area, footer_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+
+        if self.tasklist.is_empty() {
+            frame.render_widget(Paragraph::new(" The task list is empty"), list_area);
+        } else {
+            let width = self.tasklist.len().ilog10() as usize + 1;
+            let items: Vec<_> = self
+                .tasklist
+                .iter()
+                .rev()
+                .enumerate()
+                .map(|(index, task)| {
+                    let priority = match task.priority {
+                        Priority::Max => Span::styled("max", Style::new().fg(Color::Red)),
+                        Priority::Min => Span::styled("min", Style::new().fg(Color::Blue)),
+                        Priority::Value(n) => {
+                            Span::styled(n.to_string(), Style::new().fg(Color::Yellow))
+                        }
+                    };
+
+                    let message_style = if task.completed {
+                        Style::new()
+                            .add_modifier(Modifier::DIM)
+                            .add_modifier(Modifier::CROSSED_OUT)
+                    } else {
+                        Style::new().fg(Color::Green)
+                    };
+
+                    ListItem::new(Line::from(vec![
+                        Span::raw(format!(" {:>width$} | ", index + 1)),
+                        priority,
+                        Span::raw(" "),
+                        Span::styled(escape_newlines(&task.message), message_style),
+                    ]))
+                })
+                .collect();
+
+            let list =
+                List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(list, list_area, &mut self.state);
+        }
+
+        let footer = match &self.editing {
+            Some(buffer) => Line::from(vec![
+                Span::styled(" Message: ", Style::new().add_modifier(Modifier::BOLD)),
+                Span::raw(buffer.as_str()),
+                Span::styled("_", Style::new().add_modifier(Modifier::SLOW_BLINK)),
+            ]),
+            None => Line::styled(HELP, Style::new().add_modifier(Modifier::DIM)),
+        };
+
+        frame.render_widget(Paragraph::new(footer), footer_area);
+    }
+
+    fn finish_editing(&mut self) {
+        let Some(buffer) = self.editing.take() else {
+            return;
+        };
+
+        // An empty message is refused, just like with 'edit'.
+        if buffer.trim().is_empty() {
+            return;
+        }
+
+        if let Some(task) = self.selected_task() {
+            task.message = sanitize_message(&buffer);
+            task.modified_on = Some(jiff::Zoned::now());
+        }
+    }
+
+    fn move_cursor(&mut self, offset: isize) {
+        if let Some(selected) = self.state.selected() {
+            let last = self.tasklist.len() - 1;
+            self.state
+                .select(Some(selected.saturating_add_signed(offset).min(last)));
+        }
+    }
+
+    fn remove_selected(&mut self) {
+        let Some(selected) = self.state.selected() else {
+            return;
+        };
+
+        // The list is displayed in reverse, so the first row is the last task.
+        self.tasklist.remove(self.tasklist.len() - 1 - selected);
+
+        self.state.select(match self.tasklist.len() {
+            0 => None,
+            len => Some(selected.min(len - 1)),
+        });
+    }
+
+    fn selected_task(&mut self) -> Option<&mut Task> {
+        let selected = self.state.selected()?;
+        let index = self.tasklist.len().checked_sub(selected + 1)?;
+        self.tasklist.get_mut(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selected_task_is_none_on_an_empty_list() {
+        let mut tasklist = Vec::new();
+        let mut app = App {
+            tasklist: &mut tasklist,
+            state: ListState::default().with_selected(Some(0)),
+            editing: None,
+        };
+
+        assert!(app.selected_task().is_none());
+    }
+
+    #[test]
+    fn finish_editing_updates_the_message_and_the_modification_date() {
+        let mut tasklist =
+            vec![crate::parsing::parse_task(&format!("1\x1Fa\x1F{}", jiff::Zoned::now())).unwrap()];
+        let mut app = App {
+            tasklist: &mut tasklist,
+            state: ListState::default().with_selected(Some(0)),
+            editing: Some(" ".to_owned()),
+        };
+
+        app.finish_editing();
+        assert_eq!(app.tasklist[0].message, "a");
+        assert!(app.tasklist[0].modified_on.is_none());
+
+        app.editing = Some("b".to_owned());
+        app.finish_editing();
+        assert_eq!(app.tasklist[0].message, "b");
+        assert!(app.tasklist[0].modified_on.is_some());
+        assert!(app.editing.is_none());
+    }
+}
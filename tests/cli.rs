@@ -0,0 +1,1977 @@
+use std::{
+    fs,
+    io::Write,
+    path,
+    process::{self, Stdio},
+};
+
+struct TestList {
+    dir: tempfile::TempDir,
+}
+
+impl TestList {
+    fn new() -> Self {
+        let list = TestList {
+            dir: tempfile::tempdir().unwrap(),
+        };
+
+        list.run(&["init"]).assert_success();
+        list
+    }
+
+    fn path(&self) -> path::PathBuf {
+        self.dir.path().join(".ryattl")
+    }
+
+    fn write_tasks(&self, tasks: serde_json::Value) {
+        let tasklist = serde_json::json!({ "version": 1, "tasks": tasks });
+        fs::write(self.path(), tasklist.to_string()).unwrap();
+    }
+
+    fn command(&self, args: &[&str]) -> process::Command {
+        let mut command = process::Command::new(env!("CARGO_BIN_EXE_ryattl"));
+
+        // Keep the user's environment and configuration out of the tests.
+        command
+            .args(args)
+            .current_dir(self.dir.path())
+            .env_clear()
+            .env("HOME", self.dir.path())
+            .env("XDG_CONFIG_HOME", self.dir.path())
+            .env("RYATTL_FILE", self.path())
+            .env("NO_COLOR", "1");
+
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.run_with_input(args, "")
+    }
+
+    fn run_with_input(&self, args: &[&str], input: &str) -> Output {
+        run_command(self.command(args), input)
+    }
+
+    fn tasks(&self) -> Vec<serde_json::Value> {
+        let output = self.run(&["--format", "json", "list", "--all"]);
+        output.assert_success();
+
+        serde_json::from_str(&output.stdout()).unwrap()
+    }
+
+    fn messages(&self) -> Vec<String> {
+        self.tasks()
+            .into_iter()
+            .map(|task| task["message"].as_str().unwrap().to_owned())
+            .collect()
+    }
+
+    fn priorities(&self) -> Vec<String> {
+        self.tasks()
+            .into_iter()
+            .map(|task| task["priority"].to_string().trim_matches('"').to_owned())
+            .collect()
+    }
+}
+
+struct Output(process::Output);
+
+fn run_command(mut command: process::Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    Output(child.wait_with_output().unwrap())
+}
+
+impl Output {
+    fn assert_success(&self) {
+        assert!(
+            self.0.status.success(),
+            "the command failed: {}",
+            self.stderr()
+        );
+    }
+
+    fn assert_failure(&self) {
+        assert!(!self.0.status.success(), "the command succeeded");
+    }
+
+    fn stdout(&self) -> String {
+        String::from_utf8(self.0.stdout.clone()).unwrap()
+    }
+
+    fn stderr(&self) -> String {
+        String::from_utf8(self.0.stderr.clone()).unwrap()
+    }
+}
+
+#[test]
+fn add_after_current_follows_the_task_viewed_with_info() {
+    let list = TestList::new();
+
+    for message in ["a", "b", "c"] {
+        list.run(&["add", "-p", "5", message]).assert_success();
+    }
+
+    list.run(&["info", "2"]).assert_success();
+    list.run(&["add", "--after-current", "x"]).assert_success();
+    list.run(&["add", "--after-current", "y"]).assert_success();
+
+    assert_eq!(list.messages(), ["a", "b", "x", "y", "c"]);
+}
+
+#[test]
+fn info_under_dry_run_does_not_set_the_current_task() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["add", "b"]).assert_success();
+    list.run(&["--dry-run", "info", "1"]).assert_success();
+    list.run(&["add", "--after-current", "x"]).assert_success();
+
+    assert_eq!(list.messages(), ["a", "b", "x"]);
+}
+
+#[test]
+fn add_after_current_without_a_current_task_fails() {
+    let list = TestList::new();
+
+    let output = list.run(&["add", "--after-current", "x"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("there is no current task"));
+}
+
+#[test]
+fn add_asks_about_a_priority_far_above_the_highest_numeric_one() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "5", "a"]).assert_success();
+
+    let output = list.run_with_input(&["add", "-p", "5000", "b"], "n\n");
+    output.assert_failure();
+    assert!(output.stderr().contains("did you mean 'max'?"));
+
+    let output = list.run(&["add", "-p", "50", "c"]);
+    output.assert_success();
+    assert!(!output.stderr().contains("did you mean"));
+
+    list.run(&["add", "-f", "-p", "5000", "d"]).assert_success();
+
+    assert_eq!(list.messages(), ["d", "c", "a"]);
+}
+
+#[test]
+fn add_does_not_ask_about_the_priority_without_numeric_priorities() {
+    let list = TestList::new();
+
+    let output = list.run(&["add", "-p", "1500", "a"]);
+    output.assert_success();
+    assert!(!output.stderr().contains("did you mean"));
+
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "max", "a"]).assert_success();
+    list.run(&["add", "-p", "min", "b"]).assert_success();
+
+    let output = list.run(&["add", "-p", "1500", "c"]);
+    output.assert_success();
+    assert!(!output.stderr().contains("did you mean"));
+}
+
+#[test]
+fn add_asks_about_an_exact_duplicate() {
+    let list = TestList::new();
+
+    list.run(&["add", "Call Bob"]).assert_success();
+
+    let output = list.run_with_input(&["add", " call bob "], "n\n");
+    output.assert_failure();
+    assert!(output.stderr().contains("already has this task"));
+    assert!(output.stderr().contains("the task was not added"));
+
+    // EOF on stdin counts as declining.
+    list.run(&["add", "call bob"]).assert_failure();
+
+    list.run_with_input(&["add", "call bob"], "y\n")
+        .assert_success();
+    list.run(&["add", "--force", "call bob"]).assert_success();
+    list.run(&["add", "--allow-duplicate", "Call Bob"])
+        .assert_success();
+
+    assert_eq!(list.messages().len(), 4);
+}
+
+#[test]
+fn add_asks_about_a_near_duplicate() {
+    let list = TestList::new();
+
+    list.run(&["add", "Call Bob"]).assert_success();
+
+    let output = list.run_with_input(&["add", "call bob!"], "n\n");
+    output.assert_failure();
+    assert!(output.stderr().contains("already has similar tasks"));
+
+    let output = list.run(&["add", "Water the plants"]);
+    output.assert_success();
+    assert!(!output.stderr().contains("similar"));
+
+    assert_eq!(list.messages(), ["Call Bob", "Water the plants"]);
+}
+
+#[test]
+fn list_is_only_colored_when_forced_while_piped() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let output = list
+        .command(&["list"])
+        .env_remove("NO_COLOR")
+        .output()
+        .unwrap();
+    assert!(!output.stdout.contains(&b'\x1b'));
+
+    let output = list
+        .command(&["list"])
+        .env_remove("NO_COLOR")
+        .env("CLICOLOR_FORCE", "1")
+        .output()
+        .unwrap();
+    assert!(output.stdout.contains(&b'\x1b'));
+}
+
+#[test]
+fn remove_after_a_filtered_list_removes_the_task_shown() {
+    let list = TestList::new();
+
+    for message in ["a", "b", "c"] {
+        list.run(&["add", message]).assert_success();
+    }
+
+    list.run(&["done", "1"]).assert_success();
+
+    // The completed task keeps its ID while hidden.
+    let output = list.run(&["list"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), " 2 | min | b\n 3 | min | c\n");
+
+    list.run(&["remove", "--force", "2"]).assert_success();
+
+    assert_eq!(list.messages(), ["a", "c"]);
+}
+
+#[test]
+fn blank_messages_are_rejected() {
+    let list = TestList::new();
+
+    list.run(&["add", " "]).assert_failure();
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["modify", "-m", "", "1"]).assert_failure();
+    list.run(&["append", "1", " "]).assert_failure();
+
+    assert_eq!(list.messages(), ["a"]);
+}
+
+#[test]
+fn read_only_commands_do_not_take_the_lock() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let lock_file = fs::File::create(list.dir.path().join(".ryattl.lock")).unwrap();
+    lock_file.lock().unwrap();
+
+    list.run(&["list"]).assert_success();
+    list.run(&["info", "1"]).assert_success();
+
+    let output = list.run(&["add", "b"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("locked by another process"));
+}
+
+#[cfg(unix)]
+fn write_editor(list: &TestList, script: &str) -> path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let editor_path = list.dir.path().join("editor.sh");
+    fs::write(&editor_path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    fs::set_permissions(&editor_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    editor_path
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_replaces_the_message_with_what_the_editor_saved() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let editor_path = write_editor(&list, r#"printf 'edited\n' > "$1""#);
+    let output = list
+        .command(&["edit", "1"])
+        .env("EDITOR", &editor_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert_eq!(list.messages(), ["edited"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_leaves_the_message_unchanged_when_the_editor_fails_or_empties_it() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    for script in [r#"printf 'edited' > "$1"; exit 1"#, r#": > "$1""#] {
+        let editor_path = write_editor(&list, script);
+        let output = list
+            .command(&["edit", "1"])
+            .env("EDITOR", &editor_path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+    }
+
+    assert_eq!(list.messages(), ["a"]);
+}
+
+#[test]
+fn legacy_task_lists_are_rewritten_as_json_on_the_next_save() {
+    let list = TestList::new();
+    let legacy = "5\x1fa\x1f2024-05-06T07:08:09+00:00[UTC]\n\
+                  1\x1fb\x1f2024-05-07T07:08:09+00:00[UTC]\x1ftrue\n";
+
+    fs::write(list.path(), legacy).unwrap();
+
+    list.run(&["add", "c"]).assert_success();
+
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(list.path()).unwrap()).unwrap();
+    assert_eq!(stored["version"], 1);
+    assert_eq!(list.messages(), ["a", "b", "c"]);
+}
+
+#[test]
+fn remove_on_an_empty_list_says_the_list_is_empty() {
+    let list = TestList::new();
+
+    let output = list.run(&["remove", "--force", "1"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("the task list is empty"));
+}
+
+#[test]
+fn modify_sets_the_modification_date() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["modify", "-m", "b", "1"]).assert_success();
+
+    let output = list.run(&["--format", "json", "info", "1"]);
+    output.assert_success();
+
+    let task: serde_json::Value = serde_json::from_str(&output.stdout()).unwrap();
+    let modified_on: jiff::Timestamp = task["modified_on"].as_str().unwrap().parse().unwrap();
+    let created_on: jiff::Timestamp = task["created_on"].as_str().unwrap().parse().unwrap();
+    assert!(modified_on >= created_on);
+    assert!(modified_on <= jiff::Timestamp::now());
+}
+
+#[test]
+fn uuids_of_old_tasks_are_only_stored_by_commands_that_save() {
+    let list = TestList::new();
+    let legacy = "5\x1fa\x1f2024-05-06T07:08:09+00:00[UTC]\n";
+    let uuids = |list: &TestList| {
+        let output = list.run(&["--format", "json", "list"]);
+        output.assert_success();
+
+        serde_json::from_str::<Vec<serde_json::Value>>(&output.stdout()).unwrap()[0]["uuid"].clone()
+    };
+
+    fs::write(list.path(), legacy).unwrap();
+
+    list.run(&["info", "1"]).assert_success();
+    uuids(&list);
+    list.run(&["--dry-run", "done", "1"]).assert_success();
+    assert_eq!(fs::read_to_string(list.path()).unwrap(), legacy);
+    assert!(!list.dir.path().join(".ryattl.bak").exists());
+
+    list.run(&["add", "b"]).assert_success();
+    assert_eq!(uuids(&list), uuids(&list));
+}
+
+#[test]
+fn newlines_are_kept_in_messages_and_escaped_on_single_lines() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["append", "--newline", "1", "b"])
+        .assert_success();
+    assert_eq!(list.messages(), ["a\nb"]);
+
+    let output = list.run(&["search", "a"]);
+    assert_eq!(output.stdout(), " 1 | min | a\n           b\n");
+    assert_eq!(output.stdout(), list.run(&["list"]).stdout());
+
+    let output = list.run(&["export", "markdown"]);
+    assert_eq!(output.stdout(), "- [ ] a\\nb (priority: min)\n");
+
+    list.run(&["modify", "-m", "c\nd", "1"]).assert_success();
+    assert_eq!(list.messages(), ["c\nd"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_keeps_newlines() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let editor_path = write_editor(&list, r#"printf 'a\nb\n' > "$1""#);
+    let output = list
+        .command(&["edit", "1"])
+        .env("EDITOR", &editor_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert_eq!(list.messages(), ["a\nb"]);
+}
+
+#[test]
+fn top_shows_the_first_rows_of_the_list() {
+    let list = TestList::new();
+
+    for (priority, message) in [("9", "a"), ("5", "b"), ("1", "c")] {
+        list.run(&["add", "-p", priority, message]).assert_success();
+    }
+
+    let rows = list.run(&["list"]).stdout();
+    let rows: Vec<_> = rows.lines().collect();
+
+    let output = list.run(&["top"]);
+    output.assert_success();
+    assert_eq!(output.stdout().lines().collect::<Vec<_>>(), rows[..1]);
+
+    let output = list.run(&["top", "3"]);
+    output.assert_success();
+    assert_eq!(output.stdout().lines().collect::<Vec<_>>(), rows);
+
+    let output = list.run(&["top", "10"]);
+    output.assert_success();
+    assert_eq!(output.stdout().lines().collect::<Vec<_>>(), rows);
+}
+
+#[test]
+fn tasks_of_equal_priority_keep_their_order_across_saves() {
+    let list = TestList::new();
+
+    for message in ["a", "b", "c"] {
+        list.run(&["add", "-p", "5", message]).assert_success();
+    }
+
+    // The order comes from the stored sequence numbers, not the file order.
+    let mut stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(list.path()).unwrap()).unwrap();
+    stored["tasks"].as_array_mut().unwrap().reverse();
+    fs::write(list.path(), stored.to_string()).unwrap();
+
+    assert_eq!(list.messages(), ["a", "b", "c"]);
+
+    list.run(&["modify", "-p", "9", "2"]).assert_success();
+    list.run(&["modify", "-p", "5", "1"]).assert_success();
+    assert_eq!(list.messages(), ["a", "c", "b"]);
+
+    list.run(&["move", "3", "1"]).assert_success();
+    list.run(&["add", "-p", "5", "d"]).assert_success();
+    assert_eq!(list.messages(), ["b", "a", "c", "d"]);
+}
+
+#[test]
+fn done_sets_the_completion_date() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let output = list.run(&["--format", "json", "info", "1"]);
+    output.assert_success();
+    let task: serde_json::Value = serde_json::from_str(&output.stdout()).unwrap();
+    assert!(task["completed_on"].is_null());
+
+    list.run(&["done", "1"]).assert_success();
+
+    let output = list.run(&["--format", "json", "info", "1"]);
+    output.assert_success();
+
+    let task: serde_json::Value = serde_json::from_str(&output.stdout()).unwrap();
+    let completed_on: jiff::Timestamp = task["completed_on"].as_str().unwrap().parse().unwrap();
+    let created_on: jiff::Timestamp = task["created_on"].as_str().unwrap().parse().unwrap();
+    assert_eq!(task["completed"], true);
+    assert!(completed_on >= created_on);
+    assert!(completed_on <= jiff::Timestamp::now());
+}
+
+#[test]
+fn move_only_changes_the_priority_when_it_no_longer_fits() {
+    let list = TestList::new();
+
+    for (priority, message) in [("max", "a"), ("5", "b"), ("min", "c")] {
+        list.run(&["add", "-p", priority, message]).assert_success();
+    }
+
+    list.run(&["move", "2", "2"]).assert_success();
+    assert_eq!(list.messages(), ["a", "b", "c"]);
+    assert_eq!(list.priorities(), ["max", "5", "min"]);
+
+    let output = list.run(&["move", "3", "1"]);
+    output.assert_success();
+    assert!(output.stderr().contains("took over the priority"));
+    assert_eq!(list.messages(), ["c", "a", "b"]);
+    assert_eq!(list.priorities(), ["max", "max", "5"]);
+
+    let list = TestList::new();
+
+    for (priority, message) in [("9", "a"), ("5", "b"), ("5", "c"), ("1", "d")] {
+        list.run(&["add", "-p", priority, message]).assert_success();
+    }
+
+    let output = list.run(&["move", "3", "2"]);
+    output.assert_success();
+    assert!(!output.stderr().contains("took over the priority"));
+    assert_eq!(list.messages(), ["a", "c", "b", "d"]);
+    assert_eq!(list.priorities(), ["9", "5", "5", "1"]);
+
+    list.run(&["move", "4", "2"]).assert_success();
+    assert_eq!(list.messages(), ["a", "d", "c", "b"]);
+    assert_eq!(list.priorities(), ["9", "5", "5", "5"]);
+}
+
+#[test]
+fn search_shows_the_matches_like_list() {
+    let list = TestList::new();
+
+    for (priority, message) in [
+        ("max", "Call Bob"),
+        ("5", "Water the plants"),
+        ("min", "call Alice"),
+    ] {
+        list.run(&["add", "-p", priority, message]).assert_success();
+    }
+
+    let output = list.run(&["search", "call"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        " 1 | max | Call Bob\n 3 | min | call Alice\n"
+    );
+
+    let output = list.run(&["search", "--case-sensitive", "call"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), " 3 | min | call Alice\n");
+
+    // Every message contains the empty string.
+    let output = list.run(&["search", ""]);
+    output.assert_success();
+    assert_eq!(output.stdout(), list.run(&["list"]).stdout());
+
+    let output = list.run(&["search", "groceries"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+    assert!(output.stderr().contains("No matching tasks"));
+}
+
+#[test]
+fn the_last_task_of_a_batch_becomes_the_current_one() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "5", "a"]).assert_success();
+    list.run_with_input(&["add", "--batch", "-p", "5"], "b\nc\n")
+        .assert_success();
+    list.run(&["add", "-p", "5", "d"]).assert_success();
+    list.run(&["info", "3"]).assert_success();
+    list.run(&["add", "--after-current", "x"]).assert_success();
+
+    assert_eq!(list.messages(), ["a", "b", "c", "x", "d"]);
+
+    let current = fs::read_to_string(list.dir.path().join(".ryattl.current")).unwrap();
+    assert_eq!(current, list.tasks()[3]["uuid"]);
+}
+
+#[test]
+fn tasks_of_legacy_lists_can_be_the_current_one() {
+    let list = TestList::new();
+    let legacy = "5\x1fa\x1f2024-05-06T07:08:09+00:00[UTC]\n\
+                  5\x1fb\x1f2024-05-07T07:08:09+00:00[UTC]\n";
+
+    fs::write(list.path(), legacy).unwrap();
+
+    list.run(&["info", "1"]).assert_success();
+    list.run(&["add", "--after-current", "x"]).assert_success();
+
+    assert_eq!(list.messages(), ["b", "x", "a"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn the_editor_can_have_arguments() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let editor_path = write_editor(
+        &list,
+        r#"[ "$1" = --wait ] || exit 1; printf 'edited\n' > "$2""#,
+    );
+    let output = list
+        .command(&["edit", "1"])
+        .env("EDITOR", format!("{} --wait", editor_path.display()))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(list.messages(), ["edited"]);
+
+    let output = list
+        .command(&["edit", "1"])
+        .env("EDITOR", "ryattl-missing-editor --wait")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("couldn't launch 'ryattl-missing-editor --wait'"));
+}
+
+#[cfg(unix)]
+#[test]
+fn open_list_applies_a_valid_edit() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    let original = fs::read_to_string(list.path()).unwrap();
+
+    let editor_path = write_editor(
+        &list,
+        r#"printf '{"version":1,"tasks":[{"priority":5,"message":"edited","created_on":"2024-05-06T07:08:09+00:00[UTC]"}]}' > "$1""#,
+    );
+    let output = list
+        .command(&["open-list"])
+        .env("EDITOR", &editor_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert_eq!(list.messages(), ["edited"]);
+    assert_eq!(
+        fs::read_to_string(list.dir.path().join(".ryattl.bak")).unwrap(),
+        original
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn open_list_rejects_an_invalid_edit() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    let original = fs::read_to_string(list.path()).unwrap();
+
+    let editor_path = write_editor(&list, r#"printf '{"version":1,"tasks":[{}]}' > "$1""#);
+    let mut command = list.command(&["open-list"]);
+    command.env("EDITOR", &editor_path);
+
+    // Reopen the editor once, then give up.
+    let output = run_command(command, "y\nn\n");
+    output.assert_failure();
+    assert_eq!(
+        output.stderr().matches("Do you wish to reopen it?").count(),
+        2
+    );
+    assert!(output
+        .stderr()
+        .contains("corrupted at record 1: missing field `priority`"));
+    assert!(output.stderr().contains("the changes were discarded"));
+
+    assert_eq!(fs::read_to_string(list.path()).unwrap(), original);
+}
+
+#[test]
+fn remove_takes_several_ids_and_ranges() {
+    let add_tasks = |list: &TestList| {
+        for message in ["a", "b", "c", "d", "e"] {
+            list.run(&["add", message]).assert_success();
+        }
+    };
+
+    let list = TestList::new();
+    add_tasks(&list);
+
+    let output = list.run(&["remove", "--force", "1", "3", "5"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "Removed 3 tasks\n");
+    assert_eq!(list.messages(), ["b", "d"]);
+
+    let list = TestList::new();
+    add_tasks(&list);
+
+    list.run(&["remove", "--force", "2..4"]).assert_success();
+    assert_eq!(list.messages(), ["a", "e"]);
+
+    let output = list.run(&["remove", "--force", "1", "1..2"]);
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("the task ID '1' was given more than once"));
+    assert_eq!(list.messages(), ["a", "e"]);
+}
+
+fn write_fixture(list: &TestList) {
+    // Stored from the lowest priority to the highest.
+    list.write_tasks(serde_json::json!([
+        {
+            "priority": "min",
+            "message": "Water the plants",
+            "created_on": "2024-05-06T07:08:09+00:00[UTC]",
+            "completed": true,
+            "completed_on": "2024-05-09T10:00:00+00:00[UTC]",
+        },
+        {
+            "priority": 5,
+            "message": "Say \"hi\" to Bob",
+            "created_on": "2024-05-07T08:00:00+00:00[UTC]",
+        },
+        {
+            "priority": "max",
+            "message": "Pay the rent",
+            "created_on": "2024-05-08T09:30:00.5+00:00[UTC]",
+        },
+    ]));
+}
+
+#[test]
+fn export_writes_csv() {
+    let list = TestList::new();
+    write_fixture(&list);
+
+    let output = list.run(&["export", "csv"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        "id,priority,message,created_on\n\
+         1,max,\"Pay the rent\",2024-05-08T09:30:00.5+00:00\n\
+         2,5,\"Say \"\"hi\"\" to Bob\",2024-05-07T08:00:00+00:00\n\
+         3,min,\"Water the plants\",2024-05-06T07:08:09+00:00\n"
+    );
+}
+
+#[test]
+fn export_writes_markdown() {
+    let list = TestList::new();
+    write_fixture(&list);
+
+    let output = list.run(&["export", "markdown"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        "- [ ] Pay the rent (priority: max)\n\
+         - [ ] Say \"hi\" to Bob (priority: 5)\n\
+         - [x] Water the plants (priority: min)\n"
+    );
+}
+
+#[test]
+fn count_prints_the_number_of_matching_tasks() {
+    let list = TestList::new();
+
+    let output = list.run(&["count"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "0\n");
+
+    write_fixture(&list);
+    list.run(&["add", "-p", "5", "-t", "home", "Call Bob"])
+        .assert_success();
+
+    for (args, count) in [
+        (&["count"][..], "3\n"),
+        (&["count", "--all"], "4\n"),
+        (&["count", "-p", "5"], "2\n"),
+        (&["count", "-t", "home"], "1\n"),
+        (&["count", "-p", "max", "-t", "home"], "0\n"),
+    ] {
+        let output = list.run(args);
+        output.assert_success();
+        assert_eq!(output.stdout(), count, "{:?}", args);
+    }
+}
+
+#[test]
+fn undo_reverts_the_last_change_once() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["add", "b"]).assert_success();
+
+    list.run(&["undo"]).assert_success();
+    assert_eq!(list.messages(), ["a"]);
+
+    let output = list.run(&["undo"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("there is nothing to undo"));
+    assert_eq!(list.messages(), ["a"]);
+}
+
+#[test]
+fn the_search_for_a_task_list_stops_at_the_root_of_a_repository() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+
+    let repository_dir = list.dir.path().join("repository");
+    fs::create_dir_all(repository_dir.join(".git")).unwrap();
+    fs::create_dir_all(repository_dir.join("src")).unwrap();
+    fs::create_dir_all(list.dir.path().join("other").join("src")).unwrap();
+
+    let run_in = |dir: path::PathBuf| {
+        let mut command = list.command(&["list"]);
+        command.current_dir(dir).env_remove("RYATTL_FILE");
+        run_command(command, "")
+    };
+
+    let output = run_in(repository_dir.join("src"));
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("this directory has no task list associated with it"));
+
+    let output = run_in(list.dir.path().join("other").join("src"));
+    output.assert_success();
+    assert_eq!(output.stdout(), " 1 | min | a\n");
+}
+
+#[test]
+fn named_task_lists_live_side_by_side() {
+    let list = TestList::new();
+    let sub_dir = list.dir.path().join("sub");
+    fs::create_dir(&sub_dir).unwrap();
+
+    let run_in = |dir: &path::Path, args: &[&str]| {
+        let mut command = list.command(args);
+        command.current_dir(dir).env_remove("RYATTL_FILE");
+        let output = run_command(command, "");
+        output.assert_success();
+        output
+    };
+
+    run_in(list.dir.path(), &["--list", "work", "init"]);
+    assert!(list.dir.path().join(".ryattl.work").is_file());
+
+    run_in(list.dir.path(), &["add", "personal task"]);
+    run_in(&sub_dir, &["--list", "work", "add", "work task"]);
+
+    let output = run_in(&sub_dir, &["list"]);
+    assert_eq!(output.stdout(), " 1 | min | personal task\n");
+
+    let output = run_in(&sub_dir, &["--list", "work", "list"]);
+    assert_eq!(output.stdout(), " 1 | min | work task\n");
+}
+
+#[test]
+fn add_uses_the_default_priority_from_the_config_file() {
+    let list = TestList::new();
+
+    // Without a config file the default is 'min'.
+    list.run(&["add", "a"]).assert_success();
+
+    let config_dir = list.dir.path().join("ryattl");
+    fs::create_dir(&config_dir).unwrap();
+    fs::write(config_dir.join("config.toml"), "default_priority = 7\n").unwrap();
+
+    list.run(&["add", "b"]).assert_success();
+    list.run(&["add", "-p", "max", "c"]).assert_success();
+    assert_eq!(list.messages(), ["c", "b", "a"]);
+    assert_eq!(list.priorities(), ["max", "7", "min"]);
+
+    fs::write(
+        config_dir.join("config.toml"),
+        "default_priority = \"urgent\"\n",
+    )
+    .unwrap();
+
+    let output = list.run(&["add", "d"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("couldn't parse"));
+    assert!(output.stderr().contains("config.toml"));
+
+    fs::remove_file(config_dir.join("config.toml")).unwrap();
+    assert_eq!(list.messages(), ["c", "b", "a"]);
+}
+
+#[test]
+fn quiet_commands_only_print_their_output() {
+    let list = TestList::new();
+
+    let output = list.run(&["add", "--quiet", "a"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+    assert_eq!(output.stderr(), "");
+
+    let output = list.run(&["-q", "count"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "1\n");
+}
+
+#[test]
+fn uuids_survive_changes_to_the_task() {
+    let list = TestList::new();
+
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["add", "b"]).assert_success();
+
+    let uuid = list.tasks()[1]["uuid"].as_str().unwrap().to_owned();
+    list.run(&["modify", "-p", "max", "-m", "c", "2"])
+        .assert_success();
+
+    let output = list.run(&["list", "--uuid"]);
+    output.assert_success();
+    assert!(output
+        .stdout()
+        .starts_with(&format!(" 1 | max | {} | c\n", uuid)));
+    assert_eq!(list.tasks()[0]["uuid"], uuid.as_str());
+}
+
+#[test]
+fn uuid_prefixes_resolve_to_a_single_task() {
+    let list = TestList::new();
+
+    list.write_tasks(serde_json::json!([
+        {
+            "priority": "min",
+            "message": "a",
+            "created_on": "2024-05-06T07:08:09+00:00[UTC]",
+            "uuid": "abcd1111-0000-4000-8000-000000000000",
+        },
+        {
+            "priority": "min",
+            "message": "b",
+            "created_on": "2024-05-07T07:08:09+00:00[UTC]",
+            "uuid": "abcd2222-0000-4000-8000-000000000000",
+        },
+    ]));
+
+    let output = list.run(&["--format", "json", "info", "ABCD2"]);
+    output.assert_success();
+    let task: serde_json::Value = serde_json::from_str(&output.stdout()).unwrap();
+    assert_eq!(task["message"], "b");
+
+    let output = list.run(&["info", "abcd"]);
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("more than one task has a UUID starting with 'abcd'"));
+
+    let output = list.run(&["info", "ef"]);
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("no task has a UUID starting with 'ef'"));
+}
+
+#[test]
+fn exit_code_reports_queries_that_find_nothing() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "5", "a"]).assert_success();
+
+    for args in [
+        &["list", "-p", "5"][..],
+        &["search", "a"],
+        &["count"],
+        &["top"],
+    ] {
+        list.run(&[&["-e"], args].concat()).assert_success();
+    }
+
+    for args in [
+        &["list", "-p", "max"][..],
+        &["search", "b"],
+        &["count", "-p", "max"],
+    ] {
+        list.run(args).assert_success();
+        list.run(&[&["-e"], args].concat()).assert_failure();
+    }
+
+    list.run(&["done", "1"]).assert_success();
+    list.run(&["top"]).assert_success();
+    list.run(&["--exit-code", "top"]).assert_failure();
+}
+
+#[test]
+fn dry_run_remove_leaves_the_file_unchanged() {
+    let list = TestList::new();
+
+    for message in ["a", "b", "c"] {
+        list.run(&["add", message]).assert_success();
+    }
+
+    let original = fs::read(list.path()).unwrap();
+    let backup = fs::read(list.dir.path().join(".ryattl.bak")).unwrap();
+
+    let output = list.run(&["--dry-run", "remove", "1", "3"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        " 1 | min | a\n 3 | min | c\nRemoved 2 tasks (dry run)\n"
+    );
+
+    assert_eq!(fs::read(list.path()).unwrap(), original);
+    assert_eq!(
+        fs::read(list.dir.path().join(".ryattl.bak")).unwrap(),
+        backup
+    );
+}
+
+#[test]
+fn batch_add_writes_the_task_list_once() {
+    let list = TestList::new();
+
+    list.run(&["add", "first"]).assert_success();
+    let original = fs::read(list.path()).unwrap();
+
+    let messages: Vec<_> = (1..=1000).map(|n| format!("task {}", n)).collect();
+    let output = list.run_with_input(&["add", "--batch"], &(messages.join("\n") + "\n"));
+    output.assert_success();
+    assert_eq!(output.stdout(), "Added 1000 new tasks\n");
+
+    assert_eq!(list.messages()[0], "first");
+    assert_eq!(list.messages()[1..], messages);
+
+    // Writing more than once would have backed up an intermediate state.
+    assert_eq!(
+        fs::read(list.dir.path().join(".ryattl.bak")).unwrap(),
+        original
+    );
+}
+
+#[test]
+fn created_this_follows_the_calendar_across_a_month_boundary() {
+    let list = TestList::new();
+
+    list.write_tasks(serde_json::json!([
+        { "priority": "min", "message": "last year", "created_on": "2023-12-31T23:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "last month", "created_on": "2024-02-29T23:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "today", "created_on": "2024-03-01T01:00:00+00:00[UTC]" },
+    ]));
+
+    // A Friday, so the week started in the previous month.
+    let list_created_this = |period: &str| {
+        let mut command = list.command(&["list", "--created-this", period]);
+        command
+            .env("RYATTL_NOW", "2024-03-01T10:00:00+00:00[UTC]")
+            .env("TZ", "UTC");
+        let output = run_command(command, "");
+        output.assert_success();
+        output.stdout()
+    };
+
+    assert_eq!(list_created_this("today"), " 1 | min | today\n");
+    assert_eq!(
+        list_created_this("week"),
+        " 1 | min | today\n 2 | min | last month\n"
+    );
+    assert_eq!(list_created_this("month"), " 1 | min | today\n");
+    assert_eq!(
+        list_created_this("year"),
+        " 1 | min | today\n 2 | min | last month\n"
+    );
+}
+
+#[test]
+fn age_buckets_count_the_pending_tasks_by_age() {
+    let list = TestList::new();
+
+    list.write_tasks(serde_json::json!([
+        { "priority": "min", "message": "hours", "created_on": "2024-03-01T02:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "a day", "created_on": "2024-02-29T10:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "six days", "created_on": "2024-02-24T10:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "a week", "created_on": "2024-02-23T10:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "a month", "created_on": "2024-01-31T10:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "a year", "created_on": "2023-03-01T10:00:00+00:00[UTC]" },
+        {
+            "priority": "min",
+            "message": "completed",
+            "created_on": "2023-03-01T10:00:00+00:00[UTC]",
+            "completed": true,
+        },
+    ]));
+
+    let mut command = list.command(&["list", "--all", "--age-buckets"]);
+    command.env("RYATTL_NOW", "2024-03-01T10:00:00+00:00[UTC]");
+    let output = run_command(command, "");
+    output.assert_success();
+
+    let stdout = output.stdout();
+    let summary: Vec<_> = stdout.lines().skip(8).collect();
+    assert_eq!(
+        summary,
+        [
+            " < 1 day:   1",
+            " 1-7 days:  2",
+            " 1-4 weeks: 1",
+            " > 4 weeks: 2",
+        ]
+    );
+}
+
+#[test]
+fn since_commit_only_shows_the_tasks_added_after_the_commit() {
+    let list = TestList::new();
+
+    let git = |args: &[&str]| {
+        let status = process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(list.dir.path())
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+
+    list.run(&["add", "a"]).assert_success();
+    list.run(&["add", "b"]).assert_success();
+
+    git(&["init", "--quiet"]);
+    git(&["add", ".ryattl"]);
+    git(&["commit", "--quiet", "--message", "Add the first tasks"]);
+
+    list.run(&["add", "c"]).assert_success();
+    list.run(&["add", "-p", "max", "d"]).assert_success();
+
+    let run = |reference: &str| {
+        let mut command = list.command(&["list", "--since-commit", reference]);
+        command.env("PATH", std::env::var_os("PATH").unwrap());
+        run_command(command, "")
+    };
+
+    let output = run("HEAD");
+    output.assert_success();
+    assert_eq!(output.stdout(), " 1 | max | d\n 4 | min | c\n");
+
+    let output = run("no-such-ref");
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("unknown git revision 'no-such-ref'"));
+}
+
+#[test]
+fn prioritize_ranks_the_tasks_by_the_answers() {
+    let list = TestList::new();
+
+    for message in ["a", "b", "c"] {
+        list.run(&["add", message]).assert_success();
+    }
+
+    let output = list.run_with_input(&["prioritize"], "1\n");
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("no answer was given, the task list was left unchanged"));
+    assert_eq!(list.messages(), ["a", "b", "c"]);
+
+    // "b" beats "a", then "c" beats "b" without being compared to "a".
+    list.run_with_input(&["prioritize"], "1\n1\n")
+        .assert_success();
+    assert_eq!(list.messages(), ["c", "b", "a"]);
+    assert_eq!(list.priorities(), ["3", "2", "1"]);
+}
+
+#[test]
+fn gc_cleans_up_a_messy_legacy_list() {
+    let list = TestList::new();
+    let legacy = "5\x1fa\x1f2024-05-06T07:08:09+00:00[UTC]\n\
+                  \n\
+                  not a task\n\
+                  max\x1fb\x1f2999-01-01T00:00:00+00:00[UTC]\n   \n\
+                  min\x1fc\x1fyesterday\n\
+                  min\x1fd\x1f2024-05-07T07:08:09+00:00[UTC]\x1ftrue\n";
+
+    fs::write(list.path(), legacy).unwrap();
+    fs::write(
+        list.dir.path().join(".ryattl.current"),
+        "00000000-0000-0000-0000-000000000001",
+    )
+    .unwrap();
+
+    let output = list.run(&["gc"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        "Cleaned the task list\n \
+         Blank lines removed:     2\n \
+         Invalid records removed: 2\n \
+         Future dates repaired:   1\n \
+         Stale current task:      removed\n \
+         Tasks kept:              3\n"
+    );
+
+    assert!(!list.dir.path().join(".ryattl.current").exists());
+    assert!(fs::read_to_string(list.path()).unwrap().starts_with('{'));
+    assert_eq!(list.messages(), ["b", "a", "d"]);
+
+    let created_on: jiff::Timestamp = list.tasks()[0]["created_on"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(created_on <= jiff::Timestamp::now());
+}
+
+#[test]
+fn corrupted_records_are_reported_by_position() {
+    let list = TestList::new();
+    let legacy = "5\x1fa\x1f2024-05-06T07:08:09+00:00[UTC]\n\
+                  urgent\x1fb\x1f2024-05-06T07:08:09+00:00[UTC]\n\
+                  min\x1fc\x1fyesterday\n";
+
+    fs::write(list.path(), legacy).unwrap();
+
+    let output = list.run(&["list"]);
+    output.assert_failure();
+    assert_eq!(
+        output.stderr(),
+        "error: the task list file is corrupted at record 2: invalid priority\n"
+    );
+
+    let output = list.run(&["check"]);
+    output.assert_failure();
+    assert_eq!(
+        output.stdout(),
+        " record 2: invalid priority\n record 3: invalid creation date\n"
+    );
+    assert!(output
+        .stderr()
+        .contains("found 2 corrupted records, run 'gc' to drop them"));
+
+    list.write_tasks(serde_json::json!([
+        { "priority": 5, "message": "a", "created_on": "2024-05-06T07:08:09+00:00[UTC]" },
+        { "priority": 5, "message": "b" },
+    ]));
+
+    let output = list.run(&["list"]);
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("the task list file is corrupted at record 2: missing field `created_on`"));
+
+    list.run(&["gc"]).assert_success();
+
+    let output = list.run(&["check"]);
+    output.assert_success();
+    assert!(output
+        .stdout()
+        .contains("Checked 1 records, no problems found"));
+}
+
+#[test]
+fn swap_exchanges_the_top_and_the_bottom() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "max", "a"]).assert_success();
+    list.run(&["add", "-p", "5", "b"]).assert_success();
+    list.run(&["add", "c"]).assert_success();
+
+    list.run(&["swap", "1", "3"]).assert_success();
+    assert_eq!(list.messages(), ["c", "b", "a"]);
+    assert_eq!(list.priorities(), ["max", "5", "min"]);
+
+    let output = list.run(&["swap", "2", "2"]);
+    output.assert_failure();
+    assert!(output
+        .stderr()
+        .contains("a task can't be swapped with itself"));
+    assert_eq!(list.messages(), ["c", "b", "a"]);
+}
+
+#[test]
+fn bump_walks_a_task_from_the_bottom_to_the_top() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "max", "a"]).assert_success();
+    list.run(&["add", "-p", "5", "b"]).assert_success();
+    list.run(&["add", "c"]).assert_success();
+
+    let output = list.run(&["bump", "3", "up"]);
+    output.assert_success();
+    assert!(output
+        .stdout()
+        .contains("Bumped the specified task, its ID is now 2"));
+    assert_eq!(list.messages(), ["a", "c", "b"]);
+
+    list.run(&["bump", "2", "up"]).assert_success();
+    assert_eq!(list.messages(), ["c", "a", "b"]);
+    assert_eq!(list.priorities(), ["max", "5", "min"]);
+
+    let output = list.run(&["bump", "1", "up"]);
+    output.assert_success();
+    assert!(output
+        .stderr()
+        .contains("The specified task is already at the top"));
+    assert_eq!(list.messages(), ["c", "a", "b"]);
+}
+
+#[test]
+fn width_wraps_long_messages_at_word_boundaries() {
+    let list = TestList::new();
+    let message = "The quick brown fox jumps over the lazy dog and then keeps running far away";
+
+    list.run(&["add", message]).assert_success();
+
+    let output = list.run(&["list", "--width", "40"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        " 1 | min | The quick brown fox jumps\n           \
+         over the lazy dog and then\n           \
+         keeps running far away\n"
+    );
+
+    // Piped output isn't wrapped without an explicit width.
+    let output = list.run(&["list"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), format!(" 1 | min | {}\n", message));
+}
+
+#[test]
+fn reverse_keeps_the_ids_and_their_width() {
+    let list_reversed = |count: usize| {
+        let list = TestList::new();
+        let messages: String = (1..=count).map(|n| format!("t{}\n", n)).collect();
+
+        list.run_with_input(&["add", "--batch"], &messages)
+            .assert_success();
+
+        let output = list.run(&["list", "--reverse"]);
+        output.assert_success();
+        output.stdout()
+    };
+
+    assert_eq!(list_reversed(1), " 1 | min | t1\n");
+
+    let expected: String = (1..=9)
+        .rev()
+        .map(|n| format!(" {} | min | t{}\n", n, n))
+        .collect();
+    assert_eq!(list_reversed(9), expected);
+
+    let expected: String = (1..=9)
+        .rev()
+        .map(|n| format!(" {}  | min | t{}\n", n, n))
+        .collect();
+    assert_eq!(list_reversed(10), format!(" 10 | min | t10\n{}", expected));
+}
+
+#[test]
+fn purge_only_removes_the_completed_tasks() {
+    let list = TestList::new();
+
+    for message in ["a", "b", "c", "d", "e"] {
+        list.run(&["add", message]).assert_success();
+    }
+
+    list.run(&["done", "2"]).assert_success();
+    list.run(&["done", "4"]).assert_success();
+
+    let output = list.run_with_input(&["purge"], "n\n");
+    output.assert_success();
+    assert_eq!(list.messages(), ["a", "b", "c", "d", "e"]);
+
+    let output = list.run_with_input(&["purge"], "y\n");
+    output.assert_success();
+    assert!(output.stdout().contains("Purged 2 tasks"));
+    assert_eq!(list.messages(), ["a", "c", "e"]);
+
+    let output = list.run(&["list"]);
+    assert_eq!(
+        output.stdout(),
+        " 1 | min | a\n 2 | min | c\n 3 | min | e\n"
+    );
+
+    let output = list.run(&["purge", "--force"]);
+    output.assert_success();
+    assert!(output.stderr().contains("Nothing to purge"));
+}
+
+#[test]
+fn each_date_format_is_distinguishable() {
+    let list = TestList::new();
+
+    // The medium and long formats only differ in months with abbreviated names.
+    list.write_tasks(serde_json::json!([
+        { "priority": "min", "message": "a", "created_on": "2024-01-06T07:08:09+00:00[UTC]" },
+    ]));
+
+    let list_dates = |date_format: &str| {
+        let mut command = list.command(&["list", "--dates"]);
+        command
+            .env("RYATTL_DATE_FORMAT", date_format)
+            .env("TZ", "UTC")
+            .env("LANG", "en_US.UTF-8");
+        let output = run_command(command, "");
+        output.assert_success();
+        output.stdout()
+    };
+
+    let outputs = ["short", "medium", "long", "iso"].map(list_dates);
+    assert_eq!(outputs[3], " 1 | min | a | 2024-01-06 07:08:09\n");
+
+    for (index, output) in outputs.iter().enumerate() {
+        assert!(!outputs[index + 1..].contains(output), "{}", output);
+    }
+
+    assert!(outputs[1].contains("Jan 6, 2024"));
+    assert!(outputs[2].contains("January 6, 2024"));
+}
+
+#[test]
+fn limit_and_offset_page_through_the_tasks() {
+    let list = TestList::new();
+
+    list.run_with_input(&["add", "--batch"], "a\nb\nc\nd\ne\n")
+        .assert_success();
+
+    let output = list.run(&["list", "--limit", "2", "--offset", "2"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), " 3 | min | c\n 4 | min | d\n");
+    assert_eq!(output.stderr(), "showing 3–4 of 5\n");
+
+    let output = list.run(&["list", "--offset", "10"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+    assert_eq!(output.stderr(), "showing none of 5\n");
+
+    let output = list.run(&["list", "--limit", "10"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        " 1 | min | a\n 2 | min | b\n 3 | min | c\n 4 | min | d\n 5 | min | e\n"
+    );
+    assert_eq!(output.stderr(), "showing 1–5 of 5\n");
+}
+
+#[test]
+fn created_after_and_before_form_a_window() {
+    let list = TestList::new();
+
+    write_fixture(&list);
+
+    let list_created = |args: &[&str]| {
+        let mut command = list.command(&[&["list", "--all"], args].concat());
+        command.env("TZ", "UTC");
+        let output = run_command(command, "");
+        output.assert_success();
+        output.stdout()
+    };
+
+    assert_eq!(
+        list_created(&["--created-after", "2024-05-07"]),
+        " 1 | max | Pay the rent\n 2 |   5 | Say \"hi\" to Bob\n"
+    );
+    assert_eq!(
+        list_created(&["--created-before", "2024-05-07T08:01"]),
+        " 2 |   5 | Say \"hi\" to Bob\n 3 | min | Water the plants\n"
+    );
+    assert_eq!(
+        list_created(&[
+            "--created-after",
+            "2024-05-07",
+            "--created-before",
+            "2024-05-08"
+        ]),
+        " 2 | 5 | Say \"hi\" to Bob\n"
+    );
+    assert_eq!(
+        list_created(&["--created-after", "2024-05-08T09:30:00.5+00:00[UTC]"]),
+        " 1 | max | Pay the rent\n"
+    );
+}
+
+#[test]
+fn import_adds_a_task_for_each_line() {
+    let list = TestList::new();
+    let path = list.dir.path().join("todo.txt");
+
+    list.run(&["add", "-p", "max", "existing"]).assert_success();
+
+    fs::write(&path, "Buy milk\n\n   \nCall\x1fmom\nFix the sink\n").unwrap();
+
+    let output = list.run(&["import", "-p", "5", path.to_str().unwrap()]);
+    output.assert_success();
+    assert!(output.stdout().contains("Imported 3 tasks"));
+    assert_eq!(
+        list.messages(),
+        ["existing", "Buy milk", "Call mom", "Fix the sink"]
+    );
+    assert_eq!(list.priorities(), ["max", "5", "5", "5"]);
+
+    let output = list.run_with_input(&["import", "-"], "From stdin\n");
+    output.assert_success();
+    assert!(output.stdout().contains("Imported 1 tasks"));
+    assert_eq!(list.messages().len(), 5);
+    assert_eq!(list.messages()[4], "From stdin");
+
+    let output = list.run(&["import", "missing.txt"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("couldn't read 'missing.txt'"));
+}
+
+#[test]
+fn import_json_adds_the_tasks_with_defaults() {
+    let list = TestList::new();
+    let tasks = r#"[
+        { "message": "a" },
+        { "message": "b", "priority": "max", "tags": ["home", "home"] },
+        { "message": "c", "priority": 7, "due": "2024-05-06" }
+    ]"#;
+
+    let output = list.run_with_input(&["import", "--json", "-p", "3", "-"], tasks);
+    output.assert_success();
+    assert!(output.stdout().contains("Imported 3 tasks"));
+
+    let tasks = list.tasks();
+    assert_eq!(list.messages(), ["b", "c", "a"]);
+    assert_eq!(list.priorities(), ["max", "7", "3"]);
+    assert_eq!(tasks[0]["tags"], serde_json::json!(["home"]));
+    assert_eq!(tasks[0]["due"], serde_json::Value::Null);
+    assert!(tasks[1]["due"]
+        .as_str()
+        .unwrap()
+        .starts_with("2024-05-06T23:59:59"));
+    assert_eq!(tasks[2]["tags"], serde_json::json!([]));
+}
+
+#[test]
+fn import_json_rejects_the_whole_array_on_a_bad_entry() {
+    let list = TestList::new();
+
+    let inputs = [
+        (
+            r#"[{ "message": "a" }, { "priority": 5 }]"#,
+            "invalid task at index 1: missing field `message`",
+        ),
+        (
+            r#"[{ "message": "a" }, { "message": " " }]"#,
+            "invalid task at index 1: the message is empty",
+        ),
+        (
+            r#"[{ "message": "a", "due": "someday" }]"#,
+            "invalid task at index 0: expected a date",
+        ),
+        (r#"{ "message": "a" }"#, "expected a JSON array of tasks"),
+    ];
+
+    for (input, error) in inputs {
+        let output = list.run_with_input(&["import", "--json", "-"], input);
+        output.assert_failure();
+        assert!(output.stderr().contains(error), "{}", output.stderr());
+    }
+
+    assert!(list.messages().is_empty());
+}
+
+#[test]
+fn priority_filters_follow_the_order_of_min_and_max() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "min", "d"]).assert_success();
+    list.run(&["add", "-p", "5", "c"]).assert_success();
+    list.run(&["add", "-p", "100", "b"]).assert_success();
+    list.run(&["add", "-p", "max", "a"]).assert_success();
+
+    let list_ids = |args: &[&str]| {
+        let output = list.run(&[&["list", "--ids-only"], args].concat());
+        output.assert_success();
+        output.stdout()
+    };
+
+    assert_eq!(list_ids(&["--min-priority", "min"]), "1\n2\n3\n4\n");
+    assert_eq!(list_ids(&["--min-priority", "5"]), "1\n2\n3\n");
+    assert_eq!(list_ids(&["--min-priority", "6"]), "1\n2\n");
+    assert_eq!(list_ids(&["--min-priority", "max"]), "1\n");
+
+    let output = list.run(&["list", "-p", "5"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), " 3 | 5 | c\n");
+
+    let output = list.run(&["list", "-p", "5", "--min-priority", "1"]);
+    output.assert_failure();
+}
+
+#[test]
+fn priority_is_matches_exactly() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "min", "d"]).assert_success();
+    list.run(&["add", "-p", "5", "c"]).assert_success();
+    list.run(&["add", "-p", "50", "b"]).assert_success();
+    list.run(&["add", "-p", "max", "a"]).assert_success();
+    list.run(&["add", "-p", "max", "e"]).assert_success();
+
+    let list_priority_is = |priority: &str| {
+        let output = list.run(&["list", "--priority-is", priority]);
+        output.assert_success();
+        output.stdout()
+    };
+
+    assert_eq!(list_priority_is("max"), " 1 | max | a\n 2 | max | e\n");
+    assert_eq!(list_priority_is("min"), " 5 | min | d\n");
+    assert_eq!(list_priority_is("5"), " 4 | 5 | c\n");
+    assert_eq!(list_priority_is("medium"), " 3 | 50 | b\n");
+
+    let output = list.run(&["list", "--priority-is", "6"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+}
+
+#[test]
+fn page_shows_a_slice_and_the_page_count() {
+    let list = TestList::new();
+
+    list.run_with_input(&["add", "--batch"], "a\nb\nc\nd\ne\nf\ng\n")
+        .assert_success();
+
+    let output = list.run(&["list", "--page", "2", "--page-size", "3"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        " 4 | min | d\n 5 | min | e\n 6 | min | f\n"
+    );
+    assert_eq!(output.stderr(), "page 2 of 3\n");
+
+    let output = list.run(&["list", "--page", "3", "--page-size", "3"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), " 7 | min | g\n");
+    assert_eq!(output.stderr(), "page 3 of 3\n");
+}
+
+#[test]
+fn ids_only_prints_the_ids_of_the_filtered_tasks() {
+    let list = TestList::new();
+
+    list.run(&["add", "-t", "work", "Write the report"])
+        .assert_success();
+    list.run(&["add", "Water the plants"]).assert_success();
+    list.run(&["add", "-t", "work", "Review the code"])
+        .assert_success();
+    list.run(&["add", "-p", "max", "Pay the rent"])
+        .assert_success();
+
+    let output = list.run(&["list", "--tag", "work", "--ids-only"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "2\n4\n");
+
+    let output = list.run(&["search", "--ids-only", "the"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "1\n2\n3\n4\n");
+
+    let output = list.run(&["search", "--ids-only", "WATER"]);
+    output.assert_success();
+    assert_eq!(output.stdout(), "3\n");
+}
+
+#[test]
+fn priority_relative_only_changes_numeric_priorities() {
+    let list = TestList::new();
+
+    list.run(&["add", "-p", "5", "a"]).assert_success();
+
+    list.run(&["modify", "--priority-relative", "+3", "1"])
+        .assert_success();
+    assert_eq!(list.priorities(), ["8"]);
+
+    list.run(&["modify", "--priority-relative", "-2", "1"])
+        .assert_success();
+    assert_eq!(list.priorities(), ["6"]);
+
+    let output = list.run(&["modify", "--priority-relative", "x", "1"]);
+    output.assert_failure();
+    assert_eq!(list.priorities(), ["6"]);
+
+    for priority in ["max", "min"] {
+        list.run(&["modify", "-p", priority, "1"]).assert_success();
+
+        let output = list.run(&["modify", "--priority-relative", "+1", "1"]);
+        output.assert_failure();
+        assert!(output.stderr().contains(&format!(
+            "the task has the '{}' priority, only numeric priorities can be changed relatively",
+            priority
+        )));
+        assert_eq!(list.priorities(), [priority]);
+    }
+}
+
+#[test]
+fn info_shows_the_rank_and_percentile() {
+    let list = TestList::new();
+
+    list.run_with_input(&["add", "--batch"], "a\nb\nc\nd\n")
+        .assert_success();
+
+    let rank = |task_id: &str| {
+        let output = list.run(&["info", task_id]);
+        output.assert_success();
+
+        let stdout = output.stdout();
+        let line = stdout.lines().find(|line| line.contains("Rank:")).unwrap();
+        line.split_once("Rank:").unwrap().1.trim().to_owned()
+    };
+
+    assert_eq!(rank("1"), "1 of 4 (top 25%)");
+    assert_eq!(rank("2"), "2 of 4 (top 50%)");
+    assert_eq!(rank("4"), "4 of 4 (top 100%)");
+
+    list.run(&["done", "4"]).assert_success();
+    list.run(&["purge", "--force"]).assert_success();
+    assert_eq!(rank("1"), "1 of 3 (top 33%)");
+}
+
+#[test]
+fn sort_orders_by_several_keys_and_keeps_the_ids() {
+    let list = TestList::new();
+
+    list.write_tasks(serde_json::json!([
+        { "priority": "min", "message": "old min", "created_on": "2024-05-01T00:00:00+00:00[UTC]" },
+        { "priority": "min", "message": "new min", "created_on": "2024-05-03T00:00:00+00:00[UTC]" },
+        { "priority": 5, "message": "old five", "created_on": "2024-05-01T00:00:00+00:00[UTC]" },
+        { "priority": 5, "message": "new five", "created_on": "2024-05-02T00:00:00+00:00[UTC]" },
+        { "priority": "max", "message": "max", "created_on": "2024-05-01T00:00:00+00:00[UTC]" },
+    ]));
+
+    let ids = |args: &[&str]| {
+        let output = list.run(&[&["list", "--ids-only"], args].concat());
+        output.assert_success();
+        output.stdout()
+    };
+    let messages = list.messages();
+    let id_of = |message: &str| messages.iter().position(|m| m == message).unwrap() + 1;
+    let expected: String = ["max", "new five", "old five", "new min", "old min"]
+        .map(|message| format!("{}\n", id_of(message)))
+        .concat();
+
+    assert_eq!(ids(&["--sort", "priority,created:desc"]), expected);
+
+    let expected: String = ["old min", "old five", "max", "new five", "new min"]
+        .map(|message| format!("{}\n", id_of(message)))
+        .concat();
+    assert_eq!(ids(&["--sort", "created,priority:asc"]), expected);
+
+    let output = list.run(&["list", "--sort", "size"]);
+    output.assert_failure();
+}
+
+#[test]
+fn json_output_of_list_and_info_has_a_fixed_shape() {
+    let list = TestList::new();
+
+    list.write_tasks(serde_json::json!([
+        {
+            "priority": 5,
+            "message": "Say \"hi\" to Bob",
+            "created_on": "2024-05-07T08:00:00+00:00[UTC]",
+            "due": "2024-05-10T17:00:00+00:00[UTC]",
+            "tags": ["social"],
+            "uuid": "6f1c7a52-3b0e-4d8e-9d43-2b7f0a1c9e11",
+        },
+        {
+            "priority": "max",
+            "message": "Pay the rent",
+            "created_on": "2024-05-08T09:30:00.5+00:00[UTC]",
+            "modified_on": "2024-05-09T10:00:00+00:00[UTC]",
+            "uuid": "0b9e2f4d-8c1a-4f6b-a7d3-5e2c9b8f1a22",
+        },
+    ]));
+
+    let rent = serde_json::json!({
+        "id": 1,
+        "uuid": "0b9e2f4d-8c1a-4f6b-a7d3-5e2c9b8f1a22",
+        "priority": "max",
+        "message": "Pay the rent",
+        "created_on": "2024-05-08T09:30:00.5+00:00",
+        "completed": false,
+        "completed_on": null,
+        "due": null,
+        "tags": [],
+        "modified_on": "2024-05-09T10:00:00+00:00",
+    });
+    let hi = serde_json::json!({
+        "id": 2,
+        "uuid": "6f1c7a52-3b0e-4d8e-9d43-2b7f0a1c9e11",
+        "priority": 5,
+        "message": "Say \"hi\" to Bob",
+        "created_on": "2024-05-07T08:00:00+00:00",
+        "completed": false,
+        "completed_on": null,
+        "due": "2024-05-10T17:00:00+00:00",
+        "tags": ["social"],
+        "modified_on": null,
+    });
+
+    let run_json = |args: &[&str]| {
+        // Colors are forced, so any escape code would show up in the output.
+        let mut command = list.command(&[&["--format", "json"], args].concat());
+        command.env_remove("NO_COLOR").env("CLICOLOR_FORCE", "1");
+        let output = run_command(command, "");
+        output.assert_success();
+
+        let stdout = output.stdout();
+        assert!(!stdout.contains('\x1b'), "{}", stdout);
+        serde_json::from_str::<serde_json::Value>(&stdout).unwrap()
+    };
+
+    assert_eq!(run_json(&["list"]), serde_json::json!([rent, hi]));
+    assert_eq!(run_json(&["info", "2"]), hi);
+}
+
+#[test]
+fn tags_can_be_added_and_filtered_by() {
+    let list = TestList::new();
+
+    list.run(&["add", "-t", "work", "-t", "urgent", "Write the report"])
+        .assert_success();
+    list.run(&["add", "-t", " home ", "Water the plants"])
+        .assert_success();
+    list.run(&["add", "-t", "wo,rk", "Review the code"])
+        .assert_success();
+    list.run(&["add", "Pay the rent"]).assert_success();
+
+    let list_tags = |tags: &[&str]| {
+        let args: Vec<_> = tags.iter().flat_map(|tag| ["--tag", tag]).collect();
+        let output = list.run(&[&["list"], args.as_slice()].concat());
+        output.assert_success();
+        output.stdout()
+    };
+
+    assert_eq!(
+        list_tags(&["work"]),
+        " 1 | min | Write the report\n 3 | min | Review the code\n"
+    );
+    assert_eq!(
+        list_tags(&["work", "urgent"]),
+        " 1 | min | Write the report\n"
+    );
+    assert_eq!(list_tags(&["home"]), " 2 | min | Water the plants\n");
+    assert_eq!(list_tags(&["garden"]), "");
+
+    assert_eq!(
+        list.tasks()[0]["tags"],
+        serde_json::json!(["urgent", "work"])
+    );
+
+    let output = list.run(&["add", "-t", ",", "Empty tag"]);
+    output.assert_failure();
+    assert!(output.stderr().contains("expected a non-empty tag"));
+}
+
+#[test]
+fn stats_counts_the_tasks() {
+    let list = TestList::new();
+
+    write_fixture(&list);
+    list.run(&["add", "-p", "max", "Call mom"]).assert_success();
+    list.run(&["add", "-p", "3", "Fix the sink"])
+        .assert_success();
+
+    let mut command = list.command(&["stats"]);
+    command.env("RYATTL_DATE_FORMAT", "iso").env("TZ", "UTC");
+    let output = run_command(command, "");
+    output.assert_success();
+
+    let stdout = output.stdout();
+    let rows: Vec<_> = stdout.lines().take(6).collect();
+    assert_eq!(
+        rows,
+        [
+            " Total:     5",
+            " Completed: 1",
+            " Max:       2",
+            " Numeric:   2",
+            " Min:       1",
+            " Oldest:    2024-05-06 07:08:09",
+        ]
+    );
+
+    let output = list.run(&["--format", "json", "stats"]);
+    output.assert_success();
+
+    let stats: serde_json::Value = serde_json::from_str(&output.stdout()).unwrap();
+    assert_eq!(stats["total"], 5);
+    assert_eq!(stats["completed"], 1);
+    assert_eq!(
+        stats["priorities"],
+        serde_json::json!({ "max": 2, "numeric": 2, "min": 1 })
+    );
+    assert_eq!(stats["oldest"], "2024-05-06T07:08:09+00:00");
+}
+
+#[test]
+fn sort_by_age_keeps_the_ids_used_by_other_commands() {
+    let list = TestList::new();
+
+    write_fixture(&list);
+
+    let output = list.run(&["list", "--all", "--sort", "age"]);
+    output.assert_success();
+    assert_eq!(
+        output.stdout(),
+        " 3 | min | Water the plants\n 2 |   5 | Say \"hi\" to Bob\n 1 | max | Pay the rent\n"
+    );
+
+    // The first task shown still goes by its ID in the priority order.
+    let output = list.run(&["info", "3"]);
+    output.assert_success();
+    assert!(output.stdout().contains("Water the plants"));
+
+    list.run(&["remove", "--force", "3"]).assert_success();
+    assert_eq!(list.messages(), ["Pay the rent", "Say \"hi\" to Bob"]);
+}
+
+#[test]
+fn append_joins_the_text_with_a_space() {
+    let list = TestList::new();
+
+    list.run(&["add", "Buy milk"]).assert_success();
+    list.run(&["add", "-p", "max", "Pay the rent"])
+        .assert_success();
+
+    list.run(&["append", "2", "  and eggs "]).assert_success();
+    assert_eq!(list.messages(), ["Pay the rent", "Buy milk and eggs"]);
+
+    let tasks = list.tasks();
+    assert!(tasks[1]["modified_on"].is_string());
+    assert!(tasks[0]["modified_on"].is_null());
+
+    list.run(&["append", "2", "from\x1fthe store"])
+        .assert_success();
+    assert_eq!(
+        list.messages(),
+        ["Pay the rent", "Buy milk and eggs from the store"]
+    );
+
+    let output = list.run(&["list"]);
+    assert_eq!(
+        output.stdout(),
+        " 1 | max | Pay the rent\n 2 | min | Buy milk and eggs from the store\n"
+    );
+}